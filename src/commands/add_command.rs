@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use anyhow::{anyhow, Result};
 
 use crate::{
-    commands::{git_command, haxelib_command},
+    commands::{git_command, haxelib_command, install_command::InstallOptions},
     hmm::dependencies::Dependancies,
     AddArgs,
 };
@@ -12,7 +12,7 @@ pub fn add_dependency(
     add_args: AddArgs,
     deps: Dependancies,
     path: PathBuf,
-    separator: &str,
+    options: &InstallOptions,
 ) -> Result<()> {
     match &add_args.git {
         Some(git_url) => {
@@ -29,11 +29,11 @@ pub fn add_dependency(
                 &add_args.dir,
                 deps,
                 path,
-                separator,
+                options,
             )?;
         }
         None => {
-            haxelib_command::install_haxelibs(&add_args.names, deps, path)?;
+            haxelib_command::install_haxelibs(&add_args.names, deps, path, options)?;
         }
     }
 
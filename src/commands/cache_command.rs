@@ -0,0 +1,90 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+use human_bytes::human_bytes;
+use owo_colors::OwoColorize;
+
+use crate::hmm::cache::{Cache, CacheEntry, CacheEntryKind};
+
+pub fn list_cache(cache: &Cache) -> Result<()> {
+    let entries = cache.entries()?;
+    println!("Cache directory: {}", cache.root().display().bold());
+
+    if entries.is_empty() {
+        println!("The cache is empty");
+        return Ok(());
+    }
+
+    for entry in entries.iter() {
+        print_entry(entry);
+    }
+
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    println!();
+    println!(
+        "{} entries, {} total",
+        entries.len().bold(),
+        human_bytes(total as f64).bold()
+    );
+    Ok(())
+}
+
+pub fn clean_cache(cache: &Cache) -> Result<()> {
+    println!("Removing cache at {}", cache.root().display());
+    cache.clean()
+}
+
+pub fn prune_cache(cache: &Cache, days: u64) -> Result<()> {
+    let max_age = days
+        .checked_mul(60 * 60 * 24)
+        .map(Duration::from_secs)
+        .ok_or_else(|| anyhow!("--days {} is too large", days))?;
+    let removed = cache.prune(max_age)?;
+
+    for entry in removed.iter() {
+        print!("{} ", "removed".red());
+        print_entry(entry);
+    }
+
+    let freed: u64 = removed.iter().map(|e| e.size).sum();
+    println!(
+        "Pruned {} entries unused for {} days, freeing {}",
+        removed.len().bold(),
+        days,
+        human_bytes(freed as f64).bold()
+    );
+    Ok(())
+}
+
+fn print_entry(entry: &CacheEntry) {
+    let age_days = SystemTime::now()
+        .duration_since(entry.modified)
+        .unwrap_or_default()
+        .as_secs()
+        / (60 * 60 * 24);
+    let details = format!(
+        "{} - last used {} day(s) ago",
+        human_bytes(entry.size as f64),
+        age_days
+    );
+
+    match &entry.kind {
+        CacheEntryKind::HaxelibZip {
+            name,
+            version,
+            sha256,
+        } => println!(
+            "{} [haxelib]: {} ({}) {}",
+            name.green().bold(),
+            version.green(),
+            &sha256[..sha256.len().min(12)],
+            details.dimmed()
+        ),
+        CacheEntryKind::GitMirror { url } => println!(
+            "{} [git]: {} {}",
+            url.as_deref().unwrap_or("unknown remote").green().bold(),
+            entry.path.display(),
+            details.dimmed()
+        ),
+    }
+}
@@ -1,12 +1,19 @@
 use std::fs::File;
 
 use crate::hmm::dependencies::Dependancies;
+use crate::hmm::graph::{ConflictSource, DependencyGraph, VersionConflict};
 use crate::hmm::haxelib::{Haxelib, HaxelibType};
+use crate::hmm::hg;
 use anyhow::{anyhow, Context, Result};
 use console::Emoji;
 use gix::hash::Prefix;
 use std::io::Read;
 use owo_colors::OwoColorize;
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::OutputFormat;
 
 pub struct HaxelibStatus<'a> {
     pub lib: &'a Haxelib,
@@ -16,17 +23,47 @@ pub struct HaxelibStatus<'a> {
 }
 
 // First, define the install type enum
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InstallType {
     Missing,          // Needs to be installed
     MissingGit,       // Needs to be cloned
     MissingDevLink,   // Git repo present at correct commit, but subdir `.dev` link is missing
     Outdated,         // Installed but wrong version
     AlreadyInstalled, // Correctly installed
-    Conflict,         // Version conflicts between dependencies
+    Conflict,         // Local changes in a vcs checkout, or version conflicts between dependencies
     NotLocked,        // Version in hmm.json isn't locked to anything, prompt to lock?
 }
 
+impl InstallType {
+    /// Process exit code `check` uses for this status, 0 when nothing needs doing
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            InstallType::AlreadyInstalled => 0,
+            InstallType::Missing | InstallType::MissingGit | InstallType::MissingDevLink => 1,
+            InstallType::Outdated => 2,
+            InstallType::Conflict => 3,
+            InstallType::NotLocked => 4,
+        }
+    }
+}
+
+/// Returned by `check` when the project isn't in the state hmm.json describes.
+/// `main` exits with `code` instead of the generic 1 so CI can tell the cases apart
+#[derive(Debug)]
+pub struct CheckFailed {
+    pub code: i32,
+    pub message: String,
+}
+
+impl std::fmt::Display for CheckFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CheckFailed {}
+
 impl<'a> HaxelibStatus<'a> {
     pub fn new(
         lib: &'a Haxelib,
@@ -43,22 +80,158 @@ impl<'a> HaxelibStatus<'a> {
     }
 }
 
-pub fn check(deps: &Dependancies, names: &[String]) -> Result<()> {
+/// `{ name, type, install_type, wants, installed }`, the per-library document of `check --format json`
+impl Serialize for HaxelibStatus<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // correctly installed libs only carry the version in `wants`
+        let installed = match self.install_type {
+            InstallType::AlreadyInstalled => self.installed.as_ref().or(self.wants.as_ref()),
+            _ => self.installed.as_ref(),
+        };
+
+        let mut state = serializer.serialize_struct("HaxelibStatus", 5)?;
+        state.serialize_field("name", &self.lib.name)?;
+        state.serialize_field("type", &self.lib.haxelib_type)?;
+        state.serialize_field("install_type", &self.install_type)?;
+        state.serialize_field("wants", &self.wants)?;
+        state.serialize_field("installed", &installed)?;
+        state.end()
+    }
+}
+
+pub fn check(deps: &Dependancies, names: &[String], format: OutputFormat) -> Result<()> {
     let filtered = deps.filter_by_names(names);
     let total = filtered.len();
-    let installs = compare_haxelib_to_hmm(&filtered, true)?;
-    println!(
-        "{} / {} dependencie(s) are installed at the correct versions",
-        installs
+    let mut installs = match format {
+        OutputFormat::Text => compare_haxelib_to_hmm(&filtered, true)?,
+        OutputFormat::Json => filtered
             .iter()
-            .filter(|i| i.install_type == InstallType::AlreadyInstalled)
-            .count()
-            .bold(),
-        total.bold()
-    );
+            .map(|&haxelib| check_dependency(haxelib))
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    // a library whose haxelib.json can't be read still gets its status above, only the
+    // conflicts its dependencies could cause are left out
+    let graph = DependencyGraph::build(deps);
+    graph.warn_unreadable();
+    let conflicts: Vec<VersionConflict> = graph
+        .conflicts(deps)
+        .into_iter()
+        .filter(|c| {
+            names.is_empty()
+                || names.contains(&c.name)
+                || c.requirements.iter().any(|(by, _)| names.contains(by))
+        })
+        .collect();
+    for status in installs.iter_mut() {
+        // a status with a higher exit code than a conflict keeps it
+        if conflicts.iter().any(|c| c.name == status.lib.name)
+            && status.install_type.exit_code() < InstallType::Conflict.exit_code()
+        {
+            status.install_type = InstallType::Conflict;
+        }
+    }
+    let installed_count = installs
+        .iter()
+        .filter(|i| i.install_type == InstallType::AlreadyInstalled)
+        .count();
+
+    match format {
+        OutputFormat::Text => {
+            print_version_conflicts(&conflicts);
+            println!(
+                "{} / {} dependencie(s) are installed at the correct versions",
+                installed_count.bold(),
+                total.bold()
+            );
+        }
+        OutputFormat::Json => {
+            let report = json!({
+                "dependencies": installs,
+                "conflicts": conflicts.iter().map(conflict_json).collect::<Vec<_>>(),
+                "installed": installed_count,
+                "total": total,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+
+    // When several problems show up at once, the highest code wins
+    let conflict_code = match conflicts.is_empty() {
+        true => 0,
+        false => InstallType::Conflict.exit_code(),
+    };
+    let code = installs
+        .iter()
+        .map(|i| i.install_type.exit_code())
+        .chain(std::iter::once(conflict_code))
+        .max()
+        .unwrap_or(0);
+    if code != 0 {
+        let mut problems = Vec::new();
+        if installed_count < total {
+            problems.push(format!(
+                "{} / {} dependencie(s) are not installed at their hmm.json versions",
+                total - installed_count,
+                total
+            ));
+        }
+        if !conflicts.is_empty() {
+            problems.push(format!(
+                "{} version conflicts between dependencies",
+                conflicts.len()
+            ));
+        }
+        return Err(CheckFailed {
+            code,
+            message: problems.join(", "),
+        }
+        .into());
+    }
     Ok(())
 }
 
+fn conflict_json(conflict: &VersionConflict) -> Value {
+    let (version, source) = match &conflict.resolved {
+        Some((version, ConflictSource::HmmJson)) => (Some(version), Some("hmm.json")),
+        Some((version, ConflictSource::Installed)) => (Some(version), Some("installed")),
+        None => (None, None),
+    };
+    json!({
+        "name": conflict.name,
+        "version": version,
+        "source": source,
+        "requirements": conflict
+            .requirements
+            .iter()
+            .map(|(by, wants)| json!({ "by": by, "wants": wants }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn print_version_conflicts(conflicts: &[VersionConflict]) {
+    if conflicts.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", "Version conflicts:".red().bold());
+    for conflict in conflicts {
+        match &conflict.resolved {
+            Some((version, source)) => println!(
+                "{} {}",
+                conflict.name.red().bold(),
+                format!("({} {})", source, version).red()
+            ),
+            None => println!("{}", conflict.name.red().bold()),
+        }
+        for (by, wanted) in conflict.requirements.iter() {
+            println!("  {} wants {}", by.bold(), wanted.red());
+        }
+    }
+    println!();
+}
+
 pub fn compare_haxelib_to_hmm<'a>(
     haxelibs: &[&'a Haxelib],
     verbose: bool,
@@ -159,7 +332,7 @@ fn check_dependency(haxelib: &Haxelib) -> Result<HaxelibStatus<'_>> {
             let repo = match gix::discover(&repo_path) {
                 Ok(r) => r,
                 Err(e) => {
-                    println!("{}", e.to_string().red());
+                    eprintln!("{}", e.to_string().red());
 
                     return Ok(HaxelibStatus::new(
                         haxelib,
@@ -243,6 +416,58 @@ fn check_dependency(haxelib: &Haxelib) -> Result<HaxelibStatus<'_>> {
                 ));
             }
         }
+        HaxelibType::Mecurial => {
+            let repo_path = haxelib.hg_repo_path();
+
+            if !repo_path.exists() {
+                return Ok(HaxelibStatus::new(
+                    haxelib,
+                    InstallType::Missing,
+                    get_wants(haxelib),
+                    None,
+                ));
+            }
+
+            let head = hg::current_changeset(&repo_path, true)?;
+
+            if hg::is_dirty(&repo_path)? {
+                return Ok(HaxelibStatus::new(
+                    haxelib,
+                    InstallType::Conflict,
+                    get_wants(haxelib),
+                    Some(format!("{} (local changes)", head)),
+                ));
+            }
+
+            if let Some(vcs_ref) = haxelib.vcs_ref.as_deref() {
+                // An unknown ref most likely just hasn't been pulled yet
+                if hg::resolve(&repo_path, vcs_ref).as_deref() != Some(head.as_str()) {
+                    return Ok(HaxelibStatus::new(
+                        haxelib,
+                        InstallType::Outdated,
+                        get_wants(haxelib),
+                        Some(head),
+                    ));
+                }
+                current_version = vcs_ref.to_string();
+            } else {
+                current_version = head;
+            }
+
+            let has_subdir = haxelib
+                .dir
+                .as_deref()
+                .map(str::trim)
+                .is_some_and(|d| !d.is_empty());
+            if has_subdir && !lib_path.join(".dev").exists() {
+                return Ok(HaxelibStatus::new(
+                    haxelib,
+                    InstallType::MissingDevLink,
+                    get_wants(haxelib),
+                    None,
+                ));
+            }
+        }
         _ => {}
     }
 
@@ -346,7 +571,7 @@ fn print_install_status(haxelib_status: &HaxelibStatus) -> Result<()> {
 fn get_wants(haxelib: &Haxelib) -> Option<String> {
     match haxelib.haxelib_type {
         HaxelibType::Haxelib => haxelib.version.clone(),
-        HaxelibType::Git => haxelib.vcs_ref.clone(),
+        HaxelibType::Git | HaxelibType::Mecurial => haxelib.vcs_ref.clone(),
         _ => None,
     }
 }
@@ -364,6 +589,8 @@ mod tests {
             dir: None,
             url: None,
             version: Some("1.0.0".to_string()),
+            constraint: None,
+            integrity: None,
             path: None,
         };
         assert_eq!(get_wants(&haxelib), Some("1.0.0".to_string()));
@@ -375,6 +602,8 @@ mod tests {
             dir: None,
             url: None,
             version: None,
+            constraint: None,
+            integrity: None,
             path: None,
         };
         assert_eq!(get_wants(&haxelib), Some("master".to_string()));
@@ -389,6 +618,8 @@ mod tests {
             dir: None,
             url: None,
             version: None,
+            constraint: None,
+            integrity: None,
             path: Some("/some/path".to_string()),
         };
         assert_eq!(get_wants(&haxelib), None);
@@ -403,6 +634,8 @@ mod tests {
             dir: None,
             url: None,
             version: None,
+            constraint: None,
+            integrity: None,
             path: None,
         };
         assert_eq!(get_wants(&haxelib), None);
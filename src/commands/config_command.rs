@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use owo_colors::OwoColorize;
+use serde_json::{json, Value};
+
+use crate::hmm::config::{self, Layers, KEYS};
+use crate::OutputFormat;
+
+/// Prints the effective value of `key`. Strings are printed bare and lists comma separated,
+/// the way `config set` takes them
+pub fn get(layers: &Layers, key: &str) -> Result<()> {
+    config::check_key(key)?;
+    let (value, _) = layers
+        .get(key)
+        .ok_or_else(|| anyhow!("{} is not set", key))?;
+    println!("{}", display_value(&value));
+    Ok(())
+}
+
+pub fn set(path: &Path, key: &str, value: &str) -> Result<()> {
+    config::set(path, key, value)?;
+    println!("Set {} in {}", key.bold(), path.display());
+    Ok(())
+}
+
+/// Prints every setting that's set once the layers are merged, with the layer it comes from
+pub fn list(layers: &Layers, format: OutputFormat) -> Result<()> {
+    let settings: Vec<_> = KEYS
+        .iter()
+        .filter_map(|key| layers.get(key).map(|(value, source)| (*key, value, source)))
+        .collect();
+
+    match format {
+        OutputFormat::Text => {
+            if settings.is_empty() {
+                println!("No settings are set");
+            }
+            for (key, value, source) in settings.iter() {
+                println!("{} = {} {}", key, value, format!("# {}", source).dimmed());
+            }
+        }
+        OutputFormat::Json => {
+            let mut report = serde_json::Map::new();
+            for (key, value, source) in settings {
+                report.insert(
+                    key.to_string(),
+                    json!({ "value": value, "source": source.to_string() }),
+                );
+            }
+            println!("{}", serde_json::to_string_pretty(&Value::Object(report))?);
+        }
+    }
+    Ok(())
+}
+
+fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(display_value)
+            .collect::<Vec<_>>()
+            .join(","),
+        value => value.to_string(),
+    }
+}
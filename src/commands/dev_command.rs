@@ -44,6 +44,8 @@ pub fn add_dev_dependency(
         path: Some(path.to_string()),
         url: None,
         version: None,
+        constraint: None,
+        integrity: None,
     };
 
     write_dev_file(name, &absolute_path)?;
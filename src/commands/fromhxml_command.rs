@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::commands::import_command;
+use crate::hmm::{
+    dependencies::Dependancies,
+    haxelib::{Haxelib, HaxelibType},
+};
+
+/// Reads the `-lib` arguments of `hxml` (and any .hxml files it includes) into `deps`, then
+/// saves hmm.json. Libraries already in hmm.json are replaced by what the hxml says.
+pub fn import_hxml(hxml: &Path, deps: Dependancies, json_path: PathBuf) -> Result<()> {
+    let imported = read_hxml(hxml)?;
+    if imported.dependencies.is_empty() {
+        println!("No -lib arguments found in {}", hxml.display());
+        return Ok(());
+    }
+    import_command::merge_into_hmm_json(deps, imported.dependencies, json_path)
+}
+
+/// Parses an hxml file into the libraries it uses. Nested `.hxml` includes are resolved
+/// relative to the including file, and each file is only read once.
+/// When a library shows up more than once, the first occurrence wins
+pub fn read_hxml(path: &Path) -> Result<Dependancies> {
+    let mut deps = Dependancies {
+        dependencies: vec![],
+    };
+    collect_libs(path, &mut HashSet::new(), &mut deps)?;
+    Ok(deps)
+}
+
+fn collect_libs(
+    path: &Path,
+    visited: &mut HashSet<PathBuf>,
+    deps: &mut Dependancies,
+) -> Result<()> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("hxml file {} not found", path.display()))?;
+    if !visited.insert(canonical) {
+        return Ok(());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    for line in content.lines() {
+        let mut args = split_args(line).into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-lib" | "-L" | "--library" => {
+                    let spec = args.next().ok_or_else(|| {
+                        anyhow!("{}: {} is missing a library", path.display(), arg)
+                    })?;
+                    let haxelib = parse_lib_spec(&spec)?;
+                    if !deps.dependencies.iter().any(|d| d.name == haxelib.name) {
+                        deps.dependencies.push(haxelib);
+                    }
+                }
+                // the rest of the line is a shell command, which may well mention .hxml files
+                "-cmd" | "--cmd" => break,
+                include if include.ends_with(".hxml") => {
+                    collect_libs(&base.join(include), visited, deps)?
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Splits an hxml line into arguments on whitespace, keeping double quoted arguments together.
+/// Everything after a `#` outside of quotes is a comment
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in line.trim().chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes && current.is_empty() => break,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+/// Parses the argument of `-lib`, the inverse of what `to-hxml` writes:
+/// `name`, `name:version`, `name:git:url[#ref]` or `name:hg:url[#ref]`
+pub fn parse_lib_spec(spec: &str) -> Result<Haxelib> {
+    let (name, rest) = match spec.split_once(':') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (spec, None),
+    };
+    if name.is_empty() {
+        return Err(anyhow!("Invalid library '{}'", spec));
+    }
+
+    let mut haxelib = Haxelib {
+        name: name.to_string(),
+        haxelib_type: HaxelibType::Haxelib,
+        vcs_ref: None,
+        dir: None,
+        path: None,
+        url: None,
+        version: None,
+        constraint: None,
+        integrity: None,
+    };
+
+    let vcs = rest.and_then(|rest| {
+        rest.strip_prefix("git:")
+            .map(|url| (HaxelibType::Git, url))
+            .or_else(|| {
+                rest.strip_prefix("hg:")
+                    .map(|url| (HaxelibType::Mecurial, url))
+            })
+    });
+    match (vcs, rest) {
+        (Some((haxelib_type, url)), _) => {
+            // refs can't contain `#`, but urls could, so split on the last one
+            let (url, vcs_ref) = match url.rsplit_once('#') {
+                Some((url, vcs_ref)) => (url, Some(vcs_ref.to_string())),
+                None => (url, None),
+            };
+            if url.is_empty() {
+                return Err(anyhow!("Invalid library '{}': missing url", spec));
+            }
+            haxelib.haxelib_type = haxelib_type;
+            haxelib.url = Some(url.to_string());
+            haxelib.vcs_ref = vcs_ref.filter(|r| !r.is_empty());
+        }
+        (None, Some(version)) if !version.is_empty() => {
+            haxelib.version = Some(version.to_string());
+        }
+        _ => {}
+    }
+    Ok(haxelib)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn test_parse_lib_spec_haxelib() {
+        let lib = parse_lib_spec("flixel:5.2.0").unwrap();
+        assert_eq!(lib.name, "flixel");
+        assert_eq!(lib.haxelib_type, HaxelibType::Haxelib);
+        assert_eq!(lib.version.as_deref(), Some("5.2.0"));
+
+        let lib = parse_lib_spec("lime").unwrap();
+        assert_eq!(lib.name, "lime");
+        assert_eq!(lib.version, None);
+    }
+
+    #[test]
+    fn test_parse_lib_spec_vcs() {
+        let lib = parse_lib_spec("flixel:git:https://github.com/haxeflixel/flixel#master").unwrap();
+        assert_eq!(lib.haxelib_type, HaxelibType::Git);
+        assert_eq!(
+            lib.url.as_deref(),
+            Some("https://github.com/haxeflixel/flixel")
+        );
+        assert_eq!(lib.vcs_ref.as_deref(), Some("master"));
+
+        let lib = parse_lib_spec("foo:git:git@github.com:user/foo.git").unwrap();
+        assert_eq!(lib.url.as_deref(), Some("git@github.com:user/foo.git"));
+        assert_eq!(lib.vcs_ref, None);
+
+        let lib = parse_lib_spec("bar:hg:https://hg.example.com/bar#default").unwrap();
+        assert_eq!(lib.haxelib_type, HaxelibType::Mecurial);
+        assert_eq!(lib.vcs_ref.as_deref(), Some("default"));
+
+        assert!(parse_lib_spec("foo:git:").is_err());
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args("  -lib flixel:5.2.0  "),
+            vec!["-lib", "flixel:5.2.0"]
+        );
+        assert_eq!(split_args("-cp \"my src\""), vec!["-cp", "my src"]);
+        assert_eq!(split_args("# -lib commented"), Vec::<String>::new());
+        assert_eq!(split_args("-lib lime # trailing"), vec!["-lib", "lime"]);
+    }
+
+    #[test]
+    fn test_read_hxml_follows_includes() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.child("build.hxml")
+            .write_str(
+                "# build the game\n-cp source\n-lib flixel:5.2.0\nhxml/common.hxml\n--next\n-L lime\n",
+            )
+            .unwrap();
+        temp.child("hxml/common.hxml")
+            .write_str(
+                "-lib foo:git:https://example.com/foo#v1\n-lib flixel:4.0.0\n../build.hxml\n",
+            )
+            .unwrap();
+
+        let deps = read_hxml(&temp.child("build.hxml")).unwrap();
+        let names: Vec<&str> = deps.dependencies.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["flixel", "foo", "lime"]);
+        assert_eq!(deps.dependencies[0].version.as_deref(), Some("5.2.0"));
+    }
+
+    #[test]
+    fn test_read_hxml_missing_include() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.child("build.hxml")
+            .write_str("missing.hxml\n")
+            .unwrap();
+
+        let err = read_hxml(&temp.child("build.hxml")).err().unwrap();
+        assert!(err.to_string().contains("missing.hxml"));
+    }
+}
@@ -11,6 +11,8 @@ use crate::{
     },
 };
 
+use super::install_command::InstallOptions;
+
 /// Install a git-based library and add it to hmm.json
 ///
 /// # Arguments
@@ -19,6 +21,7 @@ use crate::{
 /// * `git_ref` - Optional git ref (branch, tag, or commit SHA). If None, uses repository's default branch
 /// * `deps` - Current dependencies from hmm.json
 /// * `json_path` - Path to hmm.json file
+/// * `options` - The remote separator and clone strategy to install with
 ///
 /// # Example
 /// ```bash
@@ -32,7 +35,7 @@ pub fn install_git(
     dir: &Option<String>,
     mut deps: Dependancies,
     json_path: PathBuf,
-    separator: &str,
+    options: &InstallOptions,
 ) -> Result<()> {
     // Check if library already exists in dependencies
     if let Some(existing) = deps.dependencies.iter().find(|lib| lib.name == name) {
@@ -51,6 +54,8 @@ pub fn install_git(
         path: None,
         url: Some(url.to_string()),
         version: None,
+        constraint: None,
+        integrity: None,
     };
 
     // If no ref specified, detect the default branch
@@ -61,7 +66,7 @@ pub fn install_git(
     }
 
     // Install the git repository
-    commands::install_command::install_or_update_git_cli(&haxelib_install, separator)?;
+    commands::install_command::install_or_update_git_cli(&haxelib_install, options)?;
 
     // If we didn't have a ref, get the current HEAD after clone
     if haxelib_install.vcs_ref.is_none() {
@@ -1,14 +1,14 @@
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Ok, Result};
-use reqwest::blocking::Client;
 
 use crate::{
-    commands,
+    commands::{self, install_command::InstallOptions},
     hmm::{
         self,
         dependencies::Dependancies,
         haxelib::{Haxelib, HaxelibType},
+        haxelib_api::HaxelibApi,
     },
 };
 
@@ -38,18 +38,24 @@ pub fn install_haxelibs(
     specs: &[String],
     mut deps: Dependancies,
     json_path: PathBuf,
+    options: &InstallOptions,
 ) -> Result<()> {
+    let api = HaxelibApi::for_servers(&options.servers).with_proxy(options.proxy.as_deref())?;
     for spec in specs {
         let (name, version) = parse_spec(spec)?;
-        let haxelib_install = build_haxelib_install(name, version)?;
-        commands::install_command::install_from_haxelib(&haxelib_install)?;
+        let haxelib_install = build_haxelib_install(&api, name, version)?;
+        commands::install_command::install_from_haxelib(&haxelib_install, options)?;
         deps.dependencies.push(haxelib_install);
     }
     hmm::json::save_json(deps, json_path)?;
     Ok(())
 }
 
-fn build_haxelib_install(name: &str, version: Option<&str>) -> Result<Haxelib> {
+pub fn build_haxelib_install(
+    api: &HaxelibApi,
+    name: &str,
+    version: Option<&str>,
+) -> Result<Haxelib> {
     let mut haxelib_install = Haxelib {
         name: name.to_string(),
         haxelib_type: HaxelibType::Haxelib,
@@ -58,43 +64,15 @@ fn build_haxelib_install(name: &str, version: Option<&str>) -> Result<Haxelib> {
         path: None,
         url: None,
         version: None,
+        constraint: None,
+        integrity: None,
     };
     match version {
         Some(v) => haxelib_install.version = Some(v.to_string()),
         None => {
-            // we need to query the latest version from haxelib
-            // haxelib url: lib.haxe.org/api/3.0/index.n/
-            // needs X-Haxe-Remoting header
-            // and __x param with the query
-            // in __x param, we can query with something like
-            // ay3:apiy16:getLatestVersionhay4:limeh
-            let serialized = format!("ay3:apiy16:getLatestVersionhay{}:{}h", name.len(), name);
-            let client = Client::new();
-
-            let url = format!(
-                "https://lib.haxe.org/api/3.0/index.n/?__x={}",
-                urlencoding::encode(&serialized)
-            );
-            let resp = client.get(&url).header("X-Haxe-Remoting", "1").send()?;
-
-            let resp = resp.text()?;
-            let resp_splits = resp.split(':').collect::<Vec<&str>>();
-            let encoded = resp_splits.get(1).ok_or_else(|| {
-                anyhow!(
-                    "Unexpected response from lib.haxe.org for '{}': {}",
-                    name,
-                    &resp[..resp.len().min(200)]
-                )
-            })?;
-            let decoded_resp = urlencoding::decode(encoded)?;
-
-            println!("Latest version of {} is {}", name, decoded_resp);
-
-            if decoded_resp.starts_with("No such Project") {
-                return Err(anyhow!("{}", decoded_resp));
-            }
-
-            haxelib_install.version = Some(decoded_resp.to_string());
+            let latest = api.get_latest_version(name)?;
+            println!("Latest version of {} is {}", name, latest);
+            haxelib_install.version = Some(latest);
         }
     };
     Ok(haxelib_install)
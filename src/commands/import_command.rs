@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+
+use crate::commands::haxelib_command::build_haxelib_install;
+use crate::hmm::{
+    self,
+    dependencies::Dependancies,
+    graph::{HaxelibJson, Requirement},
+    haxelib::Haxelib,
+    haxelib_api::HaxelibApi,
+};
+
+/// Adds `imported` to hmm.json, replacing the libraries it already has, and saves it
+pub fn merge_into_hmm_json(
+    mut deps: Dependancies,
+    imported: Vec<Haxelib>,
+    json_path: PathBuf,
+) -> Result<()> {
+    for haxelib in imported {
+        let label = match haxelib.version_or_ref() {
+            Ok(version) => format!("{} {}", haxelib.name, version),
+            Err(_) => haxelib.name.clone(),
+        };
+        if deps.upsert(haxelib) {
+            println!("{} {}", "Updated".yellow(), label);
+        } else {
+            println!("{} {}", "Added".green(), label);
+        }
+    }
+
+    hmm::json::save_json(deps, json_path)?;
+    Ok(())
+}
+
+/// Creates hmm.json entries from the `dependencies` of a library's own haxelib.json.
+/// `path` may be the file itself or the directory it's in.
+/// Dependencies without a version are pinned to their latest release on lib.haxe.org
+pub fn import_haxelib_json(
+    api: &HaxelibApi,
+    path: &Path,
+    deps: Dependancies,
+    json_path: PathBuf,
+) -> Result<()> {
+    let path = match path.is_dir() {
+        true => path.join("haxelib.json"),
+        false => path.to_path_buf(),
+    };
+    let file = File::open(&path).with_context(|| format!("{} not found", path.display()))?;
+    let haxelib_json: HaxelibJson = serde_json::from_reader(file)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    if haxelib_json.dependencies.is_empty() {
+        println!("{} has no dependencies", path.display());
+        return Ok(());
+    }
+
+    let mut imported = Vec::new();
+    for (name, spec) in haxelib_json.dependencies.iter() {
+        let haxelib = match Requirement::parse(spec) {
+            Requirement::Any => build_haxelib_install(api, name, None)
+                .with_context(|| format!("Failed to find the latest version of {}", name))?,
+            requirement => requirement.to_haxelib(name),
+        };
+        imported.push(haxelib);
+    }
+    merge_into_hmm_json(deps, imported, json_path)
+}
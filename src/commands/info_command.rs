@@ -0,0 +1,189 @@
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use serde_json::{json, Value};
+
+use crate::hmm::dependencies::Dependancies;
+use crate::hmm::haxelib::{lib_dir_path_for_name, HaxelibType};
+use crate::hmm::haxelib_api::{HaxelibApi, ProjectInfos};
+use crate::OutputFormat;
+
+/// Prints what lib.haxe.org knows about `lib`, with every version newest first.
+/// Marks the version hmm.json pins (if there's a hmm.json) and the one installed in `.haxelib/`
+pub fn info(
+    api: &HaxelibApi,
+    deps: Option<&Dependancies>,
+    lib: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let infos = api.infos(lib)?;
+    let pinned = deps
+        .and_then(|deps| deps.get_haxelib(lib).ok())
+        .filter(|haxelib| haxelib.haxelib_type == HaxelibType::Haxelib)
+        .and_then(|haxelib| haxelib.version.clone());
+    let installed = std::fs::read_to_string(lib_dir_path_for_name(lib).join(".current"))
+        .ok()
+        .map(|current| current.trim().to_string());
+
+    match format {
+        OutputFormat::Text => print_info(&infos, pinned.as_deref(), installed.as_deref()),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&info_json(
+                &infos,
+                pinned.as_deref(),
+                installed.as_deref()
+            ))?
+        ),
+    }
+    Ok(())
+}
+
+fn print_info(infos: &ProjectInfos, pinned: Option<&str>, installed: Option<&str>) {
+    println!(
+        "{} {} by {} ({})",
+        infos.name.green().bold(),
+        infos.curversion.bold(),
+        infos.owner,
+        infos.license
+    );
+    if !infos.desc.is_empty() {
+        println!("{}", infos.desc);
+    }
+    if !infos.website.is_empty() {
+        println!("website: {}", infos.website);
+    }
+    if !infos.tags.is_empty() {
+        println!("tags: {}", infos.tags.join(", "));
+    }
+    if !infos.contributors.is_empty() {
+        let contributors: Vec<&str> = infos.contributors.iter().map(|c| c.name.as_str()).collect();
+        println!("contributors: {}", contributors.join(", "));
+    }
+    println!("downloads: {}", infos.downloads);
+
+    println!();
+    println!("{}", "Versions:".bold());
+    for version in infos.versions.iter().rev() {
+        let mut line = format!(
+            "  {} {} {}",
+            version.name.bold(),
+            display_date(&version.date),
+            format!("({} downloads)", version.downloads).dimmed()
+        );
+        if pinned == Some(version.name.as_str()) {
+            line.push_str(&format!(" {}", "[pinned in hmm.json]".green()));
+        }
+        if installed == Some(version.name.as_str()) {
+            line.push_str(&format!(" {}", "[installed]".cyan()));
+        }
+        println!("{}", line);
+        for comment in version.comments.lines().filter(|l| !l.trim().is_empty()) {
+            println!("      {}", comment.trim());
+        }
+    }
+}
+
+fn info_json(infos: &ProjectInfos, pinned: Option<&str>, installed: Option<&str>) -> Value {
+    let versions = infos
+        .versions
+        .iter()
+        .rev()
+        .map(|version| {
+            json!({
+                "version": version.name,
+                "date": display_date(&version.date),
+                "downloads": version.downloads,
+                "comments": version.comments,
+                "pinned": pinned == Some(version.name.as_str()),
+                "installed": installed == Some(version.name.as_str()),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "name": infos.name,
+        "description": infos.desc,
+        "website": infos.website,
+        "owner": infos.owner,
+        "license": infos.license,
+        "tags": infos.tags,
+        "contributors": infos.contributors.iter().map(|c| &c.name).collect::<Vec<_>>(),
+        "downloads": infos.downloads,
+        "latest": infos.curversion,
+        "pinned": pinned,
+        "installed": installed,
+        "versions": versions,
+    })
+}
+
+/// `YYYY-MM-DD`, from either date format lib.haxe.org may send
+fn display_date(date: &str) -> String {
+    match date.parse::<f64>() {
+        // milliseconds since the epoch, converted with Howard Hinnant's `civil_from_days`
+        Ok(millis) => {
+            let days = (millis / 86_400_000.0).floor() as i64 + 719_468;
+            let era = days.div_euclid(146_097);
+            let doe = days.rem_euclid(146_097);
+            let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+            let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+            let mp = (5 * doy + 2) / 153;
+            let day = doy - (153 * mp + 2) / 5 + 1;
+            let month = if mp < 10 { mp + 3 } else { mp - 9 };
+            let year = yoe + era * 400 + i64::from(month <= 2);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        }
+        Err(_) => date.split(' ').next().unwrap_or(date).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hmm::haxelib_api::{Contributor, VersionInfos};
+
+    fn lime() -> ProjectInfos {
+        let version = |name: &str, date: &str, comments: &str| VersionInfos {
+            name: name.to_string(),
+            date: date.to_string(),
+            downloads: 10,
+            comments: comments.to_string(),
+        };
+        ProjectInfos {
+            name: "lime".to_string(),
+            desc: "A foundational Haxe framework".to_string(),
+            website: String::new(),
+            owner: "openfl".to_string(),
+            contributors: vec![Contributor {
+                name: "openfl".to_string(),
+                fullname: "OpenFL".to_string(),
+            }],
+            license: "MIT".to_string(),
+            curversion: "8.1.0".to_string(),
+            downloads: 20,
+            versions: vec![
+                version("8.0.0", "2023-01-02 03:04:05", ""),
+                version("8.1.0", "1686125350000", "fixes"),
+            ],
+            tags: vec!["cross-platform".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_display_date() {
+        assert_eq!(display_date("2023-01-02 03:04:05"), "2023-01-02");
+        assert_eq!(display_date("1686125350000"), "2023-06-07");
+        assert_eq!(display_date("0"), "1970-01-01");
+    }
+
+    #[test]
+    fn test_info_json_marks_pinned_and_installed() {
+        let json = info_json(&lime(), Some("8.0.0"), Some("8.1.0"));
+        assert_eq!(json["latest"], "8.1.0");
+        assert_eq!(
+            json["versions"],
+            json!([
+                {"version": "8.1.0", "date": "2023-06-07", "downloads": 10, "comments": "fixes", "pinned": false, "installed": true},
+                {"version": "8.0.0", "date": "2023-01-02", "downloads": 10, "comments": "", "pinned": true, "installed": false}
+            ])
+        );
+    }
+}
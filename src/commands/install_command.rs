@@ -1,25 +1,141 @@
 use crate::commands::check_command::InstallType;
+use crate::hmm;
+use crate::hmm::cache::{sha256_file, unique_suffix, Cache};
+use crate::hmm::constraint;
 use crate::hmm::dependencies::Dependancies;
+use crate::hmm::graph::{DependencyGraph, Requirement};
 use crate::hmm::haxelib::Haxelib;
 use crate::hmm::haxelib::HaxelibType;
+use crate::hmm::haxelib_api::{HaxelibApi, HaxelibServers};
+use crate::hmm::hg;
 use anyhow::Ok;
 use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
 use console::Emoji;
+use futures::stream;
 use futures_util::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use reqwest::Client as ReqwestClient;
+use std::collections::HashSet;
 use std::env;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::{self, stdin, stdout, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
 use zip::ZipArchive;
 
 use super::check_command::compare_haxelib_to_hmm;
 use super::check_command::HaxelibStatus;
+use super::haxelib_command::build_haxelib_install;
 
 pub const DEFAULT_REMOTE_SEPARATOR: &str = ".";
 
+/// How many dependencies `install` works on at once when `--jobs` isn't given
+pub const DEFAULT_JOBS: usize = 4;
+
+/// How many times a failed haxelib download is retried when `retries` isn't set
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// How long the first retry waits when `retry-delay-ms` isn't set
+pub const DEFAULT_RETRY_DELAY_MS: u64 = 500;
+
+/// Longest wait between two retries, however many there were before
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How failed haxelib downloads are retried
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each one after it
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// The `retries` and `retry-delay-ms` settings, with the defaults for whichever isn't set
+    pub fn new(retries: Option<u32>, delay_ms: Option<u64>) -> Self {
+        Self {
+            retries: retries.unwrap_or(DEFAULT_RETRIES),
+            delay: Duration::from_millis(delay_ms.unwrap_or(DEFAULT_RETRY_DELAY_MS)),
+        }
+    }
+
+    /// How long to wait before retry number `attempt`, counting from 0
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// How git dependencies that aren't in `.haxelib/` yet get cloned
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitClone {
+    /// Through a bare mirror in the user cache, shared by every project
+    #[default]
+    Mirror,
+    /// Straight from the remote, with `--filter=blob:none`
+    Blobless,
+    /// Straight from the remote, with every blob
+    Full,
+}
+
+/// Settings shared by every dependency installed during one command, like one `install_from_hmm` run
+#[derive(Clone)]
+pub struct InstallOptions {
+    /// Separator used in git remote names derived from URLs
+    pub separator: String,
+    /// Maximum number of haxelib downloads / git clones running at the same time
+    pub jobs: usize,
+    /// Only install from the user cache and never touch the network
+    pub offline: bool,
+    /// Also install (and pin) dependencies that are only required by other libraries
+    pub with_transitive: bool,
+    /// Remove and reinstall libraries even if they're already installed at the right version
+    pub force: bool,
+    /// Where haxelibs are downloaded from
+    pub servers: HaxelibServers,
+    /// How missing git dependencies are cloned
+    pub git_clone: GitClone,
+    /// Root of the user cache of haxelib zips and git mirrors
+    pub cache_dir: PathBuf,
+    /// How failed haxelib downloads are retried
+    pub retry: RetryPolicy,
+    /// Proxy for haxelib downloads, git and hg, when the standard proxy variables don't set one
+    pub proxy: Option<String>,
+}
+
+impl InstallOptions {
+    pub fn cache(&self) -> Cache {
+        Cache::at(&self.cache_dir)
+    }
+}
+
+/// A library name paired with the error that stopped it from installing
+type InstallFailure = (String, anyhow::Error);
+
+/// Why a download attempt stopped short
+enum DownloadError {
+    /// Worth another try: the server couldn't be reached, answered with a 5xx, or the
+    /// connection dropped partway through
+    Transient(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for DownloadError {
+    fn from(e: anyhow::Error) -> Self {
+        DownloadError::Fatal(e)
+    }
+}
+
+impl From<io::Error> for DownloadError {
+    fn from(e: io::Error) -> Self {
+        DownloadError::Fatal(e.into())
+    }
+}
+
 /// Resolve the remote-name separator: CLI flag > $HMM_REMOTE_SEPARATOR > default.
 /// Empty strings are treated as "not set" and fall through.
 pub fn resolve_remote_separator(flag: Option<&str>) -> String {
@@ -49,104 +165,906 @@ enum ConflictResolution {
     Skip,    // Skip this library
 }
 
-pub fn install_from_hmm(deps: &Dependancies, libs: &[String], separator: &str) -> Result<()> {
+pub fn install_from_hmm(
+    deps: &Dependancies,
+    libs: &[String],
+    options: &InstallOptions,
+) -> Result<()> {
     super::init_command::ensure_haxelib_folder()?;
 
     let filtered = deps.filter_by_names(libs);
     let installs_needed = compare_haxelib_to_hmm(&filtered, false)?;
     println!(
-        "{} dependencies need to be installed",
-        installs_needed.len().to_string().bold()
+        "{} dependencies need to be {}",
+        installs_needed.len().to_string().bold(),
+        match options.force {
+            true => "reinstalled",
+            false => "installed",
+        }
     );
 
+    if options.offline {
+        check_offline_availability(&installs_needed, &options.cache())?;
+    }
+    let client = hmm::proxy::client(options.proxy.as_deref())?;
+
+    let mut failures: Vec<InstallFailure> = Vec::new();
+    let mut queued: Vec<Haxelib> = Vec::new();
+    let mut set_aside: Vec<SetAside> = Vec::new();
+
     for install_status in installs_needed.iter() {
-        match &install_status.install_type {
-            InstallType::Missing => handle_install(install_status, separator)?,
-            InstallType::MissingGit => handle_install(install_status, separator)?,
-            InstallType::MissingDevLink => ensure_git_subdir_dev_link(install_status.lib)?,
-            InstallType::Outdated => match &install_status.lib.haxelib_type {
-                HaxelibType::Haxelib => install_from_haxelib(install_status.lib)?,
-                HaxelibType::Git => install_or_update_git_cli(install_status.lib, separator)?,
-                lib_type => println!(
-                    "{}: Installing from {:?} not yet implemented",
-                    install_status.lib.name.red(),
-                    lib_type
-                ),
-            },
-            InstallType::Conflict => {
-                // Handle git conflicts interactively
-                handle_git_conflict(install_status, separator)?;
+        let lib = install_status.lib;
+        if options.force {
+            match set_aside_for_reinstall(lib, &mut set_aside) {
+                std::result::Result::Ok(true) => queued.push(lib.clone()),
+                std::result::Result::Ok(false) => {}
+                Err(e) => failures.push((lib.name.clone(), e)),
+            }
+            continue;
+        }
+        let result = match &install_status.install_type {
+            InstallType::Missing | InstallType::MissingGit | InstallType::Outdated => {
+                queued.push(lib.clone());
+                Ok(())
             }
-            InstallType::AlreadyInstalled => (), // do nothing on things already installed at the right version
-            _ => println!(
-                "{} {:?}: Not implemented",
-                install_status.lib.name, install_status.install_type
-            ),
+            InstallType::MissingDevLink => ensure_subdir_dev_link(lib),
+            InstallType::Conflict if lib.haxelib_type == HaxelibType::Mecurial => Err(anyhow!(
+                "{} has uncommitted changes in {}, commit or revert them before installing",
+                lib.name,
+                lib.hg_repo_path().display()
+            )),
+            // Conflicts prompt the user, so they are resolved one at a time before the
+            // concurrent installs start writing to the terminal
+            InstallType::Conflict => handle_git_conflict(install_status, options),
+            InstallType::AlreadyInstalled => Ok(()), // do nothing on things already installed at the right version
+            _ => {
+                println!(
+                    "{} {:?}: Not implemented",
+                    lib.name, install_status.install_type
+                );
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            failures.push((lib.name.clone(), e));
         }
     }
 
-    Ok(())
+    failures.extend(run_install_jobs(&client, queued, options));
+
+    // a reinstall only replaces the old install once the new one made it
+    for aside in set_aside {
+        let name = aside.name.clone();
+        let result = match failures.iter().any(|(failed, _)| *failed == name) {
+            true => aside.restore(),
+            false => aside.discard(),
+        };
+        if let Err(e) = result {
+            failures.push((name, e));
+        }
+    }
+
+    let result = report_install_failures(&failures);
+    if !options.with_transitive {
+        let graph = DependencyGraph::build(deps);
+        graph.warn_unreadable();
+        report_unpinned_transitive(&graph);
+    }
+    result
+}
+
+/// An install moved out of the way by `reinstall`, kept until its replacement is in place
+struct SetAside {
+    name: String,
+    lib_path: PathBuf,
+    old: PathBuf,
 }
 
-pub fn handle_install(haxelib_status: &HaxelibStatus, separator: &str) -> Result<()> {
-    match &haxelib_status.lib.haxelib_type {
-        HaxelibType::Haxelib => install_from_haxelib(haxelib_status.lib)?,
-        HaxelibType::Git => install_or_update_git_cli(haxelib_status.lib, separator)?,
-        lib_type => println!(
-            "{}: Installing from {:?} not yet implemented",
-            haxelib_status.lib.name.red(),
-            lib_type
-        ),
+impl SetAside {
+    /// Puts the old install back, in place of whatever the failed reinstall left
+    fn restore(self) -> Result<()> {
+        if self.lib_path.exists() {
+            std::fs::remove_dir_all(&self.lib_path)
+                .with_context(|| format!("Failed to remove {}", self.lib_path.display()))?;
+        }
+        std::fs::rename(&self.old, &self.lib_path)
+            .with_context(|| format!("Failed to restore {}", self.lib_path.display()))
     }
 
-    Ok(())
+    fn discard(self) -> Result<()> {
+        std::fs::remove_dir_all(&self.old)
+            .with_context(|| format!("Failed to remove {}", self.old.display()))
+    }
+}
+
+/// Moves `.haxelib/<name>` into `set_aside` so `lib` gets downloaded or cloned again. Dev
+/// dependencies are left alone, and a git or hg checkout with local changes is only replaced
+/// if the user agrees. Returns whether `lib` should be installed
+fn set_aside_for_reinstall(lib: &Haxelib, set_aside: &mut Vec<SetAside>) -> Result<bool> {
+    let dirty = match lib.haxelib_type {
+        HaxelibType::Dev => {
+            println!("{} is a dev dependency, skipping", lib.name.yellow().bold());
+            return Ok(false);
+        }
+        HaxelibType::Git => {
+            let repo_path = lib.git_repo_path();
+            repo_path.exists() && git_is_dirty(&repo_path)?
+        }
+        HaxelibType::Mecurial => {
+            let repo_path = lib.hg_repo_path();
+            repo_path.exists() && hg::is_dirty(&repo_path)?
+        }
+        HaxelibType::Haxelib => false,
+    };
+    if dirty && !confirm_discard(lib)? {
+        println!("Skipping {}", lib.name.yellow().bold());
+        return Ok(false);
+    }
+
+    let lib_path = lib.lib_dir_path();
+    if lib_path.exists() {
+        let name = lib_path.file_name().unwrap_or_default().to_string_lossy();
+        let old = lib_path.with_file_name(format!(".{}.old-{}", name, unique_suffix()));
+        std::fs::rename(&lib_path, &old)
+            .with_context(|| format!("Failed to move {} aside", lib_path.display()))?;
+        set_aside.push(SetAside {
+            name: lib.name.clone(),
+            lib_path,
+            old,
+        });
+    }
+    Ok(true)
+}
+
+fn git_is_dirty(repo_path: &Path) -> Result<bool> {
+    let output = std::process::Command::new("git")
+        .args(["-C", path_to_str(repo_path)?, "status", "--porcelain"])
+        .output()
+        .context("Failed to execute git status")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git status failed in {}: {}",
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(!output.stdout.is_empty())
 }
 
+/// Asks before a reinstall throws away local changes. Anything but yes (including no input
+/// at all, like in CI) keeps them
+fn confirm_discard(lib: &Haxelib) -> Result<bool> {
+    println!(
+        "{} {}",
+        lib.name.yellow().bold(),
+        "has uncommitted changes, reinstalling will delete them".yellow()
+    );
+    print!("Reinstall {} anyway? (y/N): ", lib.name);
+    stdout().flush()?;
+
+    let mut input = String::new();
+    stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Pins every haxelib whose `version` is missing or outside its `constraint` to the highest
+/// release within it, saving hmm.json if anything changed. A `version` that already satisfies
+/// its constraint is kept, so installs stay reproducible
+pub fn resolve_constraints(
+    api: &HaxelibApi,
+    mut deps: Dependancies,
+    libs: &[String],
+    json_path: PathBuf,
+    offline: bool,
+) -> Result<Dependancies> {
+    let names: Vec<String> = deps
+        .filter_by_names(libs)
+        .iter()
+        .map(|lib| lib.name.clone())
+        .collect();
+    let mut resolved_count = 0;
+
+    for lib in deps.dependencies.iter_mut() {
+        if !names.contains(&lib.name) || lib.haxelib_type != HaxelibType::Haxelib {
+            continue;
+        }
+        let Some(range) = lib.constraint.clone() else {
+            continue;
+        };
+        let req = constraint::parse(&range).with_context(|| format!("{} in hmm.json", lib.name))?;
+        if lib
+            .version
+            .as_deref()
+            .is_some_and(|v| constraint::matches(&req, v))
+        {
+            continue;
+        }
+        if offline {
+            return Err(anyhow!(
+                "{}: no version within {} is pinned, and lib.haxe.org can't be asked while offline",
+                lib.name,
+                range
+            ));
+        }
+
+        let versions = api.versions(&lib.name)?;
+        let versions: Vec<&str> = versions.iter().map(|v| v.name.as_str()).collect();
+        let version = constraint::resolve(&req, &versions)
+            .ok_or_else(|| anyhow!("{}: no release matches {}", lib.name, range))?;
+        println!(
+            "Resolved {} {} to {}",
+            lib.name.bold(),
+            range,
+            version.green()
+        );
+        lib.version = Some(version.to_string());
+        // the old hash belongs to the old version
+        lib.integrity = None;
+        resolved_count += 1;
+    }
+
+    if resolved_count > 0 {
+        hmm::json::save_json(deps.clone(), json_path)?;
+    }
+    Ok(deps)
+}
+
+/// Installs the dependencies of installed libraries that hmm.json doesn't list, and pins
+/// them into hmm.json. Repeats until the newly installed ones don't pull in anything else.
+pub fn install_transitive_deps(
+    mut deps: Dependancies,
+    json_path: PathBuf,
+    options: &InstallOptions,
+) -> Result<()> {
+    let api = HaxelibApi::for_servers(&options.servers).with_proxy(options.proxy.as_deref())?;
+    let client = hmm::proxy::client(options.proxy.as_deref())?;
+    let mut attempted: HashSet<String> = HashSet::new();
+    let mut pinned_count = 0;
+    let mut failures: Vec<InstallFailure> = Vec::new();
+
+    let graph = loop {
+        let graph = DependencyGraph::build(&deps);
+        let mut queued = Vec::new();
+        for node in graph.unpinned() {
+            if !attempted.insert(node.name.clone()) {
+                continue;
+            }
+            match resolve_transitive(&api, &graph, &node.name, options.offline) {
+                std::result::Result::Ok(lib) => queued.push(lib),
+                Err(e) => failures.push((node.name.clone(), e)),
+            }
+        }
+
+        if queued.is_empty() {
+            break graph;
+        }
+
+        println!(
+            "Installing {} transitive dependencies...",
+            queued.len().to_string().bold()
+        );
+        let round_failures = run_install_jobs(&client, queued.clone(), options);
+        for lib in queued {
+            if !round_failures.iter().any(|(name, _)| name == &lib.name) {
+                deps.dependencies.push(lib);
+                pinned_count += 1;
+            }
+        }
+        failures.extend(round_failures);
+    };
+    // only the last round, so libraries that stay unreadable are reported once
+    graph.warn_unreadable();
+
+    if pinned_count > 0 {
+        hmm::json::save_json(deps, json_path)?;
+    }
+    report_install_failures(&failures)
+}
+
+/// Picks what to install for the transitive dependency `name`: a vcs source if one is asked
+/// for, otherwise the highest version asked for, otherwise the latest release
+fn resolve_transitive(
+    api: &HaxelibApi,
+    graph: &DependencyGraph,
+    name: &str,
+    offline: bool,
+) -> Result<Haxelib> {
+    let requirements: Vec<&Requirement> = graph
+        .dependents(name)
+        .into_iter()
+        .map(|(_, requirement)| requirement)
+        .collect();
+
+    if let Some(vcs) = requirements
+        .iter()
+        .find(|r| matches!(r, Requirement::Vcs { .. }))
+    {
+        return Ok(vcs.to_haxelib(name));
+    }
+
+    let highest = requirements
+        .iter()
+        .filter_map(|r| match r {
+            Requirement::Version(v) => Some(v.as_str()),
+            _ => None,
+        })
+        .max_by(
+            |a, b| match (semver::Version::parse(a), semver::Version::parse(b)) {
+                (std::result::Result::Ok(a), std::result::Result::Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            },
+        );
+
+    match highest {
+        Some(version) => Ok(Requirement::Version(version.to_string()).to_haxelib(name)),
+        None if offline => Err(anyhow!(
+            "no version is required, and the latest one can't be looked up while offline"
+        )),
+        None => build_haxelib_install(api, name, None),
+    }
+}
+
+/// Lists libraries that installed libraries depend on, but that hmm.json doesn't pin
+fn report_unpinned_transitive(graph: &DependencyGraph) {
+    let unpinned = graph.unpinned();
+    if unpinned.is_empty() {
+        return;
+    }
+
+    println!();
+    println!(
+        "{} {}",
+        unpinned.len().to_string().yellow().bold(),
+        "transitive dependencies are not pinned in hmm.json:".yellow()
+    );
+    for node in unpinned {
+        let required_by = graph
+            .dependents(&node.name)
+            .iter()
+            .map(|(by, requirement)| format!("{} ({})", by, requirement))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let status = match node.path {
+            Some(_) => "installed",
+            None => "not installed",
+        };
+        println!(
+            "  {} [{}] required by {}",
+            node.name.yellow().bold(),
+            status,
+            required_by
+        );
+    }
+    println!(
+        "Run {} to install and pin them",
+        "hmm-rs install --with-transitive".bold()
+    );
+}
+
+/// Installs `libs` under a single tokio runtime, running at most `options.jobs` at once.
+/// Every install runs to completion; the errors of the ones that failed are returned.
 #[tokio::main]
-pub async fn install_from_haxelib(haxelib: &Haxelib) -> Result<()> {
+async fn run_install_jobs(
+    client: &ReqwestClient,
+    libs: Vec<Haxelib>,
+    options: &InstallOptions,
+) -> Vec<InstallFailure> {
+    let progress = MultiProgress::new();
+
+    let results: Vec<(String, Result<()>)> = stream::iter(libs)
+        .map(|lib| {
+            let progress = &progress;
+            async move {
+                let name = lib.name.clone();
+                (
+                    name,
+                    install_dependency(client, progress, lib, options).await,
+                )
+            }
+        })
+        .buffer_unordered(options.jobs.max(1))
+        .collect()
+        .await;
+
+    results
+        .into_iter()
+        .filter_map(|(name, result)| result.err().map(|e| (name, e)))
+        .collect()
+}
+
+async fn install_dependency(
+    client: &ReqwestClient,
+    progress: &MultiProgress,
+    lib: Haxelib,
+    options: &InstallOptions,
+) -> Result<()> {
+    match &lib.haxelib_type {
+        HaxelibType::Haxelib => download_and_install_haxelib(client, progress, &lib, options).await,
+        // git is driven through its CLI, so keep it off the async worker threads
+        HaxelibType::Git => {
+            let options = options.clone();
+            tokio::task::spawn_blocking(move || install_or_update_git_cli(&lib, &options)).await?
+        }
+        HaxelibType::Mecurial => {
+            let options = options.clone();
+            tokio::task::spawn_blocking(move || install_or_update_hg_cli(&lib, &options)).await?
+        }
+        lib_type => {
+            println!(
+                "{}: Installing from {:?} not yet implemented",
+                lib.name.red(),
+                lib_type
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Makes sure everything `installs` needs is already in the cache (or in `.haxelib/`),
+/// failing with the full list of what's missing before any install starts.
+fn check_offline_availability(installs: &[HaxelibStatus], cache: &Cache) -> Result<()> {
+    let mut missing = Vec::new();
+
+    for status in installs {
+        let lib = status.lib;
+        let needs_source = matches!(
+            status.install_type,
+            InstallType::Missing
+                | InstallType::MissingGit
+                | InstallType::Outdated
+                | InstallType::Conflict
+        );
+        if !needs_source {
+            continue;
+        }
+
+        let problem = match lib.haxelib_type {
+            HaxelibType::Haxelib => match lib.try_version() {
+                None => Some("no version pinned in hmm.json".to_string()),
+                Some(version) => cache
+                    .find_haxelib_zip(&lib.name, version, lib.integrity.as_deref())
+                    .is_none()
+                    .then(|| {
+                        format!(
+                            "{} is not in the cache ({})",
+                            version,
+                            cache.haxelib_version_dir(&lib.name, version).display()
+                        )
+                    }),
+            },
+            HaxelibType::Git => {
+                let wanted = lib.try_vcs_ref().unwrap_or("HEAD");
+                let local_repo = lib.git_repo_path();
+                let mirror = lib.try_url().map(|url| (url, cache.git_mirror_path(url)));
+
+                match mirror {
+                    _ if local_repo.exists() && git_has_commit(&local_repo, wanted) => None,
+                    None => Some("no url in hmm.json".to_string()),
+                    Some((url, mirror)) if !mirror.exists() => {
+                        Some(format!("no cached mirror of {}", url))
+                    }
+                    Some((url, mirror)) if !git_has_commit(&mirror, wanted) => {
+                        Some(format!("{} is not in the cached mirror of {}", wanted, url))
+                    }
+                    Some(_) => None,
+                }
+            }
+            // Mercurial repos aren't mirrored, so they only work offline once cloned
+            HaxelibType::Mecurial => {
+                let wanted = lib.try_vcs_ref().unwrap_or("tip");
+                hg::resolve(&lib.hg_repo_path(), wanted).is_none().then(|| {
+                    format!(
+                        "{} has not been pulled into {}",
+                        wanted,
+                        lib.hg_repo_path().display()
+                    )
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(problem) = problem {
+            missing.push(format!(
+                "  {} [{:?}]: {}",
+                lib.name, lib.haxelib_type, problem
+            ));
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Cannot install offline, {} dependencies are not available locally:\n{}",
+        missing.len(),
+        missing.join("\n")
+    ))
+}
+
+/// Whether `git_ref` resolves to a commit in the repository (or bare mirror) at `repo_path`
+fn git_has_commit(repo_path: &Path, git_ref: &str) -> bool {
+    let Some(repo) = repo_path.to_str() else {
+        return false;
+    };
+    std::process::Command::new("git")
+        .args([
+            "-C",
+            repo,
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", git_ref),
+        ])
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Prints every collected install error, and fails if there were any.
+fn report_install_failures(failures: &[InstallFailure]) -> Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    println!();
     println!(
-        "Downloading: {} - {} - {}",
-        haxelib.name.bold(),
-        "lib.haxe.org".yellow().bold(),
-        haxelib.download_url()?.bold()
+        "{} {}",
+        failures.len().to_string().red().bold(),
+        "dependencies failed to install:".red()
     );
+    for (name, e) in failures {
+        println!("  {}: {:#}", name.red().bold(), e);
+    }
+
+    Err(anyhow!("Failed to install {} dependencies", failures.len()))
+}
+
+#[tokio::main]
+pub async fn install_from_haxelib(haxelib: &Haxelib, options: &InstallOptions) -> Result<()> {
+    let client = hmm::proxy::client(options.proxy.as_deref())?;
+    let zip_path =
+        cached_or_downloaded_zip(&client, &MultiProgress::new(), haxelib, options).await?;
+    install_haxelib_zip(haxelib, zip_path).await
+}
+
+async fn download_and_install_haxelib(
+    client: &ReqwestClient,
+    progress: &MultiProgress,
+    haxelib: &Haxelib,
+    options: &InstallOptions,
+) -> Result<()> {
+    let zip_path = cached_or_downloaded_zip(client, progress, haxelib, options).await?;
+    install_haxelib_zip(haxelib, zip_path).await
+}
 
-    let response = ReqwestClient::new()
-        .get(haxelib.download_url()?)
-        .send()
-        .await?;
+async fn install_haxelib_zip(haxelib: &Haxelib, zip_path: PathBuf) -> Result<()> {
+    verify_haxelib_integrity(haxelib, &zip_path)?;
 
-    if !response.status().is_success() {
-        return Err(anyhow!("Failed to download: HTTP {}", response.status()));
+    // unzipping is blocking filesystem work
+    let lib = haxelib.clone();
+    tokio::task::spawn_blocking(move || extract_haxelib_zip(&lib, &zip_path)).await??;
+
+    print_success(haxelib)?;
+    Ok(())
+}
+
+/// Returns the cached zip of the haxelib, downloading it into the cache first if needed
+#[tokio::main]
+pub async fn fetch_haxelib_zip(haxelib: &Haxelib, options: &InstallOptions) -> Result<PathBuf> {
+    let client = hmm::proxy::client(options.proxy.as_deref())?;
+    cached_or_downloaded_zip(&client, &MultiProgress::new(), haxelib, options).await
+}
+
+/// With `options.force` (from `reinstall`) the cache is skipped and the zip downloaded again,
+/// unless `options.offline`
+async fn cached_or_downloaded_zip(
+    client: &ReqwestClient,
+    progress: &MultiProgress,
+    haxelib: &Haxelib,
+    options: &InstallOptions,
+) -> Result<PathBuf> {
+    let cache = options.cache();
+    let (offline, refresh) = (options.offline, options.force);
+    let version = haxelib.version()?;
+
+    // with an integrity, only the zip it names will do, anything else is downloaded again
+    let cached = match refresh && !offline {
+        true => None,
+        false => cache.find_haxelib_zip(&haxelib.name, version, haxelib.integrity.as_deref()),
+    };
+    let zip_path = match cached {
+        Some(cached) => {
+            println!(
+                "Using cached: {} - {} - {}",
+                haxelib.name.bold(),
+                version.bright_green(),
+                cached.display()
+            );
+            cache.touch(&cached);
+            cached
+        }
+        None if offline => {
+            return Err(anyhow!(
+                "{} {} is not in the cache, and can't be downloaded while offline",
+                haxelib.name,
+                version
+            ));
+        }
+        None => {
+            let download = cache.download_path(&haxelib.name, version)?;
+            if let Err(e) =
+                download_haxelib_zip(client, progress, haxelib, options, &download).await
+            {
+                let _ = std::fs::remove_file(&download);
+                return Err(e);
+            }
+            cache.store_haxelib_zip(&haxelib.name, version, &download)?
+        }
+    };
+
+    Ok(zip_path)
+}
+
+/// Refuses a zip whose sha256 doesn't match the `integrity` recorded in hmm.json
+fn verify_haxelib_integrity(haxelib: &Haxelib, zip_path: &Path) -> Result<()> {
+    let Some(expected) = haxelib.integrity.as_deref() else {
+        return Ok(());
+    };
+
+    let actual = sha256_file(zip_path)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!(
+            "Integrity check failed for {} {}: expected sha256 {}, got {} ({})",
+            haxelib.name,
+            haxelib.version()?,
+            expected,
+            actual,
+            zip_path.display()
+        ));
     }
 
-    let expected_total_size = response
-        .content_length()
-        .ok_or_else(|| anyhow!("Server didn't provide content length"))?;
+    Ok(())
+}
+
+/// Refuses a checkout whose tree hash doesn't match the `integrity` recorded in hmm.json
+fn verify_git_integrity(haxelib: &Haxelib, repo_path: &Path) -> Result<()> {
+    let Some(expected) = haxelib.integrity.as_deref() else {
+        return Ok(());
+    };
+
+    let output = std::process::Command::new("git")
+        .args(["-C", path_to_str(repo_path)?, "rev-parse", "HEAD^{tree}"])
+        .output()
+        .context("Failed to execute git rev-parse")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Could not read the tree hash of {}",
+            repo_path.display()
+        ));
+    }
 
-    let pb: ProgressBar = ProgressBar::new(expected_total_size);
-    pb.set_style(ProgressStyle::with_template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.yellow/red}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
-             .unwrap());
+    let actual = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!(
+            "Integrity check failed for {} at {}: expected tree {}, got {}",
+            haxelib.name,
+            haxelib.vcs_ref()?,
+            expected,
+            actual
+        ));
+    }
 
-    let tmp_dir = env::temp_dir().join(format!("{}.zip", haxelib.name));
+    Ok(())
+}
 
-    let _ = {
-        let mut file = File::create(&tmp_dir)?;
-        let mut downloaded: u64 = 0;
-        let mut stream = response.bytes_stream();
+/// Streams the haxelib zip into `destination`, with a progress bar. Tries the haxelib server,
+/// then each mirror, until one of them can be reached and doesn't answer with a 5xx.
+/// Each server gets the retries of `options.retry` before moving on to the next
+async fn download_haxelib_zip(
+    client: &ReqwestClient,
+    progress: &MultiProgress,
+    haxelib: &Haxelib,
+    options: &InstallOptions,
+    destination: &Path,
+) -> Result<()> {
+    let retry = &options.retry;
+    let mut unavailable = Vec::new();
+    for server in options.servers.urls() {
+        let url = haxelib.download_url(server)?;
+        println!(
+            "Downloading: {} - {} - {}",
+            haxelib.name.bold(),
+            server.yellow().bold(),
+            url.bold()
+        );
 
-        while let Some(item) = stream.next().await {
-            let chunk = item?;
-            file.write_all(&chunk)?;
-            let new = std::cmp::min(downloaded + (chunk.len() as u64), expected_total_size);
-            downloaded = new;
-            pb.set_position(new);
+        match download_with_retries(client, progress, haxelib, &url, destination, retry).await {
+            std::result::Result::Ok(()) => return Ok(()),
+            Err(DownloadError::Transient(e)) => {
+                println!("{} is unavailable: {:#}", server.yellow(), e);
+                unavailable.push(server.as_str());
+                // the next server's zip may not be byte for byte the same, so don't resume it
+                let _ = std::fs::remove_file(destination);
+            }
+            Err(DownloadError::Fatal(e)) => return Err(e),
         }
+    }
 
-        file.flush()?;
-        downloaded
+    Err(anyhow!(
+        "Failed to download: no haxelib server could be reached (tried {})",
+        unavailable.join(", ")
+    ))
+}
+
+/// Downloads `url` into `destination`, retrying transient failures with exponential backoff.
+/// Whatever an earlier attempt left in `destination` is resumed with a Range request
+/// instead of being downloaded again
+async fn download_with_retries(
+    client: &ReqwestClient,
+    progress: &MultiProgress,
+    haxelib: &Haxelib,
+    url: &str,
+    destination: &Path,
+    retry: &RetryPolicy,
+) -> std::result::Result<(), DownloadError> {
+    let mut attempt = 0;
+    loop {
+        match download_attempt(client, progress, haxelib, url, destination).await {
+            Err(DownloadError::Transient(e)) if attempt < retry.retries => {
+                let delay = retry.backoff(attempt);
+                attempt += 1;
+                println!(
+                    "{}: {:#}, retrying in {}ms ({}/{})",
+                    haxelib.name.yellow(),
+                    e,
+                    delay.as_millis(),
+                    attempt,
+                    retry.retries
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A single request for `url`, appending to the partial download in `destination` if any
+async fn download_attempt(
+    client: &ReqwestClient,
+    progress: &MultiProgress,
+    haxelib: &Haxelib,
+    url: &str,
+    destination: &Path,
+) -> std::result::Result<(), DownloadError> {
+    let resume_from = std::fs::metadata(destination).map_or(0, |m| m.len());
+    let mut request = client.get(url);
+    if resume_from > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+    }
+
+    let response = match request.send().await {
+        std::result::Result::Ok(response) => response,
+        Err(e) if e.is_builder() => return Err(DownloadError::Fatal(e.into())),
+        Err(e) => return Err(DownloadError::Transient(e.into())),
     };
 
+    let status = response.status();
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(DownloadError::Transient(anyhow!("HTTP {}", status)));
+    }
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // what's on disk doesn't fit the file on the server, so start over
+        std::fs::remove_file(destination)?;
+        return Err(DownloadError::Transient(anyhow!(
+            "HTTP {}, the partial download can't be resumed",
+            status
+        )));
+    }
+    if !status.is_success() {
+        return Err(DownloadError::Fatal(anyhow!(
+            "Failed to download: HTTP {}",
+            status
+        )));
+    }
+
+    // a 200 means the server ignored the Range header and is sending the whole zip again
+    let offset = match status {
+        reqwest::StatusCode::PARTIAL_CONTENT => {
+            let start = response
+                .headers()
+                .get(reqwest::header::CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(content_range_start);
+            if start != Some(resume_from) {
+                std::fs::remove_file(destination)?;
+                return Err(DownloadError::Transient(anyhow!(
+                    "the server resumed the download at the wrong offset"
+                )));
+            }
+            resume_from
+        }
+        _ => 0,
+    };
+
+    write_haxelib_zip(progress, haxelib, response, destination, offset).await
+}
+
+/// Where the bytes of a `Content-Range: bytes <start>-<end>/<total>` response start
+fn content_range_start(content_range: &str) -> Option<u64> {
+    content_range
+        .trim()
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Writes the body of `response` into `destination` from `offset` on. Shows a progress bar
+/// when the server says how big the zip is, and a spinner when it doesn't
+async fn write_haxelib_zip(
+    progress: &MultiProgress,
+    haxelib: &Haxelib,
+    response: reqwest::Response,
+    destination: &Path,
+    offset: u64,
+) -> std::result::Result<(), DownloadError> {
+    let expected_total_size = response.content_length().map(|length| offset + length);
+
+    let pb: ProgressBar = match expected_total_size {
+        Some(total) => {
+            let pb = progress.add(ProgressBar::new(total));
+            pb.set_style(ProgressStyle::with_template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.yellow/red}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                     .unwrap());
+            pb
+        }
+        None => {
+            let pb = progress.add(ProgressBar::new_spinner());
+            pb.set_style(
+                ProgressStyle::with_template(
+                    "{msg}\n{spinner:.green} [{elapsed_precise}] {bytes} ({bytes_per_sec})",
+                )
+                .unwrap(),
+            );
+            pb.enable_steady_tick(Duration::from_millis(100));
+            pb
+        }
+    };
+    pb.set_position(offset);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(destination)?;
+    let mut stream = response.bytes_stream();
+
+    while let Some(item) = stream.next().await {
+        let chunk = match item {
+            std::result::Result::Ok(chunk) => chunk,
+            Err(e) => {
+                // keep what made it to disk, the next attempt picks up from there
+                file.flush()?;
+                pb.abandon();
+                return Err(DownloadError::Transient(e.into()));
+            }
+        };
+        file.write_all(&chunk)?;
+        pb.inc(chunk.len() as u64);
+    }
+    file.flush()?;
+
+    let downloaded = std::fs::metadata(destination)?.len();
+    if let Some(expected) = expected_total_size.filter(|expected| *expected != downloaded) {
+        pb.abandon();
+        return Err(DownloadError::Transient(anyhow!(
+            "Download incomplete: expected {} bytes, got {} bytes",
+            expected,
+            downloaded
+        )));
+    }
+
     let finish_message = format!(
         "{}: {} done downloading from {}",
         haxelib.name.green().bold(),
@@ -155,15 +1073,14 @@ pub async fn install_from_haxelib(haxelib: &Haxelib) -> Result<()> {
     );
     pb.finish_with_message(finish_message);
 
-    let metadata = std::fs::metadata(&tmp_dir)?;
-    if metadata.len() != expected_total_size {
-        return Err(anyhow!(
-            "Download incomplete: expected {} bytes, got {} bytes",
-            expected_total_size,
-            metadata.len()
-        ));
-    }
+    std::result::Result::Ok(())
+}
 
+/// Extracts a haxelib zip into `.haxelib/<name>/<version>`.
+/// The zip is unpacked into a staging folder next to it, which is renamed into place once
+/// complete, and `.current` is only written after that. So an install that fails or is
+/// interrupted never leaves a half extracted version behind
+fn extract_haxelib_zip(haxelib: &Haxelib, zip_path: &Path) -> Result<()> {
     let output_dir = haxelib.lib_dir_path();
 
     if let Err(e) = std::fs::create_dir(&output_dir) {
@@ -175,16 +1092,29 @@ pub async fn install_from_haxelib(haxelib: &Haxelib) -> Result<()> {
         }
     }
 
-    create_current_file(&output_dir, &haxelib.version()?.to_string())?;
+    let version_dir = haxelib.version_as_commas()?;
+    let unzipped_output_dir = output_dir.join(&version_dir);
+    let staging_dir = output_dir.join(format!(".{}.staging-{}", version_dir, unique_suffix()));
+
+    if let Err(e) = unzip_haxelib(zip_path, &staging_dir)
+        .and_then(|_| move_into_place(&staging_dir, &unzipped_output_dir))
+    {
+        let _ = std::fs::remove_dir_all(&staging_dir);
+        return Err(e);
+    }
+
+    create_current_file(&output_dir, &haxelib.version()?.to_string())
+}
 
-    // unzipping
+/// Unzips a haxelib into `unzipped_output_dir`, dropping the folder the zip may wrap it in
+fn unzip_haxelib(zip_path: &Path, unzipped_output_dir: &Path) -> Result<()> {
     let archive =
-        File::open(&tmp_dir).context(format!("Failed to open downloaded zip: {:?}", tmp_dir))?;
+        File::open(zip_path).context(format!("Failed to open downloaded zip: {:?}", zip_path))?;
 
     let mut zip_file =
         ZipArchive::new(archive).context("Error opening zip file - file may be corrupted")?;
 
-    let unzipped_output_dir = output_dir.join(haxelib.version_as_commas()?);
+    std::fs::create_dir_all(unzipped_output_dir)?;
 
     // Find the base path by locating the shallowest haxelib.json in the ZIP.
     // Some haxelib packages nest all files under a wrapper directory (e.g. "release/"),
@@ -206,7 +1136,9 @@ pub async fn install_from_haxelib(haxelib: &Haxelib) -> Result<()> {
         best.unwrap_or_default()
     };
 
-    // Extract entries, stripping the base_path prefix
+    // Extract entries, stripping the base_path prefix. Symlinks are made once everything else
+    // is in place, so no entry is ever written through one
+    let mut symlinks = Vec::new();
     for i in 0..zip_file.len() {
         let mut entry = zip_file.by_index(i)?;
         let full_name = entry.name().replace('\\', "/");
@@ -229,36 +1161,154 @@ pub async fn install_from_haxelib(haxelib: &Haxelib) -> Result<()> {
 
         if entry.is_dir() {
             std::fs::create_dir_all(&out_path)?;
+        } else if entry.is_symlink() {
+            let mut target = String::new();
+            io::Read::read_to_string(&mut entry, &mut target)?;
+            symlinks.push((relative.to_string(), target));
         } else {
             if let Some(parent) = out_path.parent() {
                 std::fs::create_dir_all(parent)?;
             }
             let mut outfile = File::create(&out_path)?;
             io::copy(&mut entry, &mut outfile)?;
+            let extended = entry.extra_data_fields().find_map(|field| match field {
+                zip::ExtraField::ExtendedTimestamp(timestamp) => timestamp.mod_time(),
+                _ => None,
+            });
+            let modified = match extended {
+                Some(seconds) => Some(std::time::UNIX_EPOCH + Duration::from_secs(seconds.into())),
+                None => entry.last_modified().and_then(zip_time),
+            };
+            if let Some(modified) = modified {
+                outfile.set_modified(modified)?;
+            }
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                use std::os::unix::fs::PermissionsExt;
+                // setuid, setgid and sticky bits have no business in a library
+                let permissions = std::fs::Permissions::from_mode(mode & 0o777);
+                std::fs::set_permissions(&out_path, permissions)?;
+            }
         }
     }
 
-    std::fs::remove_file(&tmp_dir)?;
+    for (link, target) in symlinks {
+        let out_path = unzipped_output_dir.join(&link);
+        if !symlink_stays_inside(&link, &target) {
+            eprintln!(
+                "{}: skipping symlink {} -> {}, it points outside the library",
+                "Warning".yellow(),
+                link,
+                target
+            );
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // a path of the same name extracted as a file or folder wins over the link
+        if out_path.symlink_metadata().is_ok() {
+            continue;
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, &out_path)?;
+        // making symlinks needs extra privileges on Windows, so there the link stays a file
+        // holding its target
+        #[cfg(not(unix))]
+        std::fs::write(&out_path, &target)?;
+    }
 
-    print_success(haxelib)?;
+    Ok(())
+}
+
+/// Whether a zip symlink at `link` pointing to `target` (both relative to the extracted folder)
+/// stays inside that folder. Absolute targets are refused, and `..` may only lead the target,
+/// since climbing back out of a folder that is itself a link could end up anywhere
+fn symlink_stays_inside(link: &str, target: &str) -> bool {
+    if target.is_empty() || target.starts_with(['/', '\\']) || target.contains(':') {
+        return false;
+    }
+    let mut depth = link
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .count()
+        .saturating_sub(1);
+    let mut descended = false;
+    for part in target.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." if descended => return false,
+            ".." => match depth.checked_sub(1) {
+                Some(parent) => depth = parent,
+                None => return false,
+            },
+            _ => descended = true,
+        }
+    }
+    true
+}
+
+/// A DOS zip timestamp as a `SystemTime`. It's in the packer's local time with no zone, so
+/// taking it as UTC is deliberately approximate: off by the packer's UTC offset. Only used when
+/// the entry has no extended timestamp (0x5455), which is in UTC
+fn zip_time(time: zip::DateTime) -> Option<std::time::SystemTime> {
+    if !time.is_valid() {
+        return None;
+    }
+    // days since the epoch, with Howard Hinnant's `days_from_civil`
+    let (month, day) = (time.month() as i64, time.day() as i64);
+    let year = time.year() as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds =
+        days * 86400 + time.hour() as i64 * 3600 + time.minute() as i64 * 60 + time.second() as i64;
+    let seconds = u64::try_from(seconds).ok()?;
+    Some(std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Renames `staging` to `target`. A `target` left by an earlier install is moved aside first,
+/// and only removed once the new one is in place
+fn move_into_place(staging: &Path, target: &Path) -> Result<()> {
+    let old = target.exists().then(|| {
+        let name = target.file_name().unwrap_or_default().to_string_lossy();
+        target.with_file_name(format!(".{}.old-{}", name, unique_suffix()))
+    });
+    if let Some(old) = &old {
+        std::fs::rename(target, old)
+            .with_context(|| format!("Failed to move {} aside", target.display()))?;
+    }
+
+    if let Err(e) = std::fs::rename(staging, target) {
+        if let Some(old) = &old {
+            let _ = std::fs::rename(old, target);
+        }
+        return Err(e).with_context(|| format!("Failed to move {} into place", target.display()));
+    }
+
+    if let Some(old) = old {
+        let _ = std::fs::remove_dir_all(old);
+    }
     Ok(())
 }
 
 /// Unified git installer using git CLI for optimal performance and reliability
-/// - Uses blobless clone (--filter=blob:none) for fast initial download with full history
+/// - Clones through the bare mirror in the user cache, so repos are only downloaded once
+///   (unless `options.git_clone` says otherwise)
 /// - Smart checkout: tries local first, fetches only if commit not found
 /// - Properly handles submodules with --init --recursive
-pub fn install_or_update_git_cli(haxelib: &Haxelib, separator: &str) -> Result<()> {
+/// - When `options.offline`, only ever reads from the cached mirror
+pub fn install_or_update_git_cli(haxelib: &Haxelib, options: &InstallOptions) -> Result<()> {
     let git_dir_path = haxelib.git_repo_path();
     let parent_dir = haxelib.lib_dir_path();
 
     // Ensure repository exists (clone if needed)
     if !git_dir_path.exists() {
-        println!(
-            "Cloning {} (blobless for speed + full history)...",
-            haxelib.name
-        );
-        clone_blobless_git_repo(haxelib, &git_dir_path, separator)?;
+        println!("Cloning {}...", haxelib.name);
+        clone_git_repo(haxelib, &git_dir_path, options)?;
 
         // Create .current file indicating this is a git install
         create_current_file(&parent_dir, &String::from("git"))?;
@@ -268,24 +1318,181 @@ pub fn install_or_update_git_cli(haxelib: &Haxelib, separator: &str) -> Result<(
 
     // Checkout the specified commit/ref (if provided)
     if haxelib.vcs_ref.is_some() {
-        smart_checkout_git_ref(haxelib, &git_dir_path, separator)?;
+        smart_checkout_git_ref(haxelib, &git_dir_path, options)?;
     } else {
         println!("No ref specified, using repository's default branch");
     }
+    verify_git_integrity(haxelib, &git_dir_path)?;
 
     // Update submodules to match the checked out commit
-    update_git_submodules(&git_dir_path)?;
+    update_git_submodules(&git_dir_path, options)?;
 
     // If a subdirectory is configured, point a `.dev` marker into it.
-    ensure_git_subdir_dev_link(haxelib)?;
+    ensure_subdir_dev_link(haxelib)?;
+
+    print_success(haxelib)?;
+    Ok(())
+}
+
+/// Mercurial counterpart of `install_or_update_git_cli`: clones into `.haxelib/<name>/hg`,
+/// pulls only when the wanted changeset isn't known yet, then updates the working copy.
+pub fn install_or_update_hg_cli(haxelib: &Haxelib, options: &InstallOptions) -> Result<()> {
+    let offline = options.offline;
+    let proxy = options.proxy.as_deref();
+    let hg_dir_path = haxelib.hg_repo_path();
+    let wanted = haxelib.try_vcs_ref().unwrap_or("tip");
+
+    if !hg_dir_path.exists() {
+        if offline {
+            return Err(anyhow!(
+                "{} is not cloned, and can't be cloned while offline",
+                haxelib.name
+            ));
+        }
+        println!("Cloning {} (hg)...", haxelib.name);
+        hg::clone(haxelib.url()?, &hg_dir_path, proxy)?;
+        create_current_file(&haxelib.lib_dir_path(), &String::from("hg"))?;
+    } else {
+        // Without a pinned ref we follow the remote tip, so always pull in that case
+        let known = haxelib.vcs_ref.is_some() && hg::resolve(&hg_dir_path, wanted).is_some();
+        if !known && !offline {
+            println!("Pulling {} (hg)...", haxelib.name);
+            hg::pull(&hg_dir_path, proxy)?;
+        }
+    }
+
+    hg::update(&hg_dir_path, wanted)?;
+    println!("✓ Updated to {}", wanted);
+
+    ensure_subdir_dev_link(haxelib)?;
 
     print_success(haxelib)?;
     Ok(())
 }
 
+/// Clone through the bare mirror kept in the user cache, so every project (and every
+/// worktree) only downloads a repository once. Falls back to cloning straight from the
+/// remote if the mirror can't be created or updated (unless `offline`).
+/// `GitClone::Blobless` and `GitClone::Full` skip the mirror, except offline where
+/// it's the only thing there is to clone from.
+fn clone_git_repo(haxelib: &Haxelib, target_path: &Path, options: &InstallOptions) -> Result<()> {
+    let (separator, offline) = (options.separator.as_str(), options.offline);
+    let proxy = options.proxy.as_deref();
+    match options.git_clone {
+        GitClone::Blobless if !offline => {
+            return clone_blobless_git_repo(haxelib, target_path, separator, proxy)
+        }
+        GitClone::Full if !offline => {
+            return clone_full_git_repo(haxelib, target_path, separator, proxy)
+        }
+        _ => {}
+    }
+
+    let url = haxelib.url()?;
+
+    let mirror = match update_git_mirror(&options.cache(), url, offline, proxy) {
+        std::result::Result::Ok(mirror) => mirror,
+        Err(e) if offline => return Err(e),
+        Err(e) => {
+            println!(
+                "{} {:#}",
+                "Could not use the cached mirror, cloning directly:".yellow(),
+                e
+            );
+            return clone_blobless_git_repo(haxelib, target_path, separator, proxy);
+        }
+    };
+
+    let clone_result = std::process::Command::new("git")
+        .arg("clone")
+        .arg(&mirror)
+        .arg(target_path)
+        .status()
+        .context("Failed to execute git clone")?;
+
+    if !clone_result.success() {
+        return Err(anyhow!(
+            "Git clone from cached mirror {} failed for {}",
+            mirror.display(),
+            haxelib.name
+        ));
+    }
+
+    println!("✓ Clone completed (from cache)");
+
+    // origin points at the mirror; rename it and point it at the real remote instead
+    let remote_name = parse_remote_name_from_url(url, separator)?;
+    rename_origin_remote(target_path, &remote_name)?;
+    ensure_git_remote(target_path, &remote_name, url)?;
+
+    Ok(())
+}
+
+/// Creates the bare mirror of `url` in the cache, or fetches into it if it already exists.
+/// When `offline` the existing mirror is used as-is. Returns the mirror path.
+fn update_git_mirror(
+    cache: &Cache,
+    url: &str,
+    offline: bool,
+    proxy: Option<&str>,
+) -> Result<PathBuf> {
+    let mirror = cache.git_mirror_path(url);
+
+    if offline {
+        if !mirror.exists() {
+            return Err(anyhow!("No cached mirror of {} to use offline", url));
+        }
+        cache.touch(&mirror);
+        return Ok(mirror);
+    }
+
+    if mirror.exists() {
+        println!("Updating cached mirror {}...", mirror.display());
+        let fetch_result = std::process::Command::new("git")
+            .arg("--git-dir")
+            .arg(&mirror)
+            .args(["remote", "update", "--prune"])
+            .envs(hmm::proxy::command_env(proxy))
+            .status()
+            .context("Failed to execute git remote update")?;
+
+        if !fetch_result.success() {
+            return Err(anyhow!("Failed to update cached mirror of {}", url));
+        }
+        cache.touch(&mirror);
+        return Ok(mirror);
+    }
+
+    if let Some(parent) = mirror.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    println!("Mirroring {} into the cache...", url);
+    let mirror_result = std::process::Command::new("git")
+        .args(["clone", "--mirror", url])
+        .arg(&mirror)
+        .envs(hmm::proxy::command_env(proxy))
+        .status()
+        .context("Failed to execute git clone --mirror")?;
+
+    if !mirror_result.success() {
+        // don't leave a half-written mirror behind for the next install to trip over
+        let _ = std::fs::remove_dir_all(&mirror);
+        return Err(anyhow!("Failed to mirror {}", url));
+    }
+
+    Ok(mirror)
+}
+
 /// Clone with --filter=blob:none for fast download with full commit history
 /// Falls back to regular clone if blobless is not supported
-fn clone_blobless_git_repo(haxelib: &Haxelib, target_path: &Path, separator: &str) -> Result<()> {
+fn clone_blobless_git_repo(
+    haxelib: &Haxelib,
+    target_path: &Path,
+    separator: &str,
+    proxy: Option<&str>,
+) -> Result<()> {
     let url = haxelib.url()?;
 
     // Try blobless clone first (fast, full history)
@@ -296,27 +1503,45 @@ fn clone_blobless_git_repo(haxelib: &Haxelib, target_path: &Path, separator: &st
             url,
             path_to_str(target_path)?,
         ])
+        .envs(hmm::proxy::command_env(proxy))
         .status()
         .context("Failed to execute git clone")?;
 
-    if blobless_result.success() {
-        println!("✓ Blobless clone completed");
-    } else {
+    if !blobless_result.success() {
         // Fallback to regular clone if blobless not supported
         println!("Blobless clone failed, falling back to regular clone...");
-        let regular_result = std::process::Command::new("git")
-            .args(["clone", url, path_to_str(target_path)?])
-            .status()
-            .context("Failed to execute git clone")?;
+        return clone_full_git_repo(haxelib, target_path, separator, proxy);
+    }
+    println!("✓ Blobless clone completed");
 
-        if !regular_result.success() {
-            return Err(anyhow!("Git clone failed for {}", haxelib.name));
-        }
+    // Parse remote name from URL and rename origin
+    let remote_name = parse_remote_name_from_url(url, separator)?;
+    rename_origin_remote(target_path, &remote_name)?;
+
+    Ok(())
+}
+
+/// Regular clone straight from the remote, downloading every blob of its history
+fn clone_full_git_repo(
+    haxelib: &Haxelib,
+    target_path: &Path,
+    separator: &str,
+    proxy: Option<&str>,
+) -> Result<()> {
+    let url = haxelib.url()?;
 
-        println!("✓ Clone completed");
+    let regular_result = std::process::Command::new("git")
+        .args(["clone", url, path_to_str(target_path)?])
+        .envs(hmm::proxy::command_env(proxy))
+        .status()
+        .context("Failed to execute git clone")?;
+
+    if !regular_result.success() {
+        return Err(anyhow!("Git clone failed for {}", haxelib.name));
     }
 
-    // Parse remote name from URL and rename origin
+    println!("✓ Clone completed");
+
     let remote_name = parse_remote_name_from_url(url, separator)?;
     rename_origin_remote(target_path, &remote_name)?;
 
@@ -324,19 +1549,23 @@ fn clone_blobless_git_repo(haxelib: &Haxelib, target_path: &Path, separator: &st
 }
 
 /// Smart checkout: try local first, fetch if commit not found
-fn smart_checkout_git_ref(haxelib: &Haxelib, repo_path: &Path, separator: &str) -> Result<()> {
+fn smart_checkout_git_ref(
+    haxelib: &Haxelib,
+    repo_path: &Path,
+    options: &InstallOptions,
+) -> Result<()> {
     let target_ref = haxelib.vcs_ref()?;
     let url = haxelib.url()?;
 
     println!("Checking out {} at {}...", haxelib.name, target_ref);
 
     // Ensure remote exists with correct name and URL
-    let remote_name = parse_remote_name_from_url(url, separator)?;
+    let remote_name = parse_remote_name_from_url(url, &options.separator)?;
     ensure_git_remote(repo_path, &remote_name, url)?;
 
     // Try to checkout locally first
     let checkout_result = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "checkout", target_ref])
+        .args(["-C", path_to_str(repo_path)?, "checkout", target_ref])
         .output()
         .context("Failed to execute git checkout")?;
 
@@ -345,14 +1574,46 @@ fn smart_checkout_git_ref(haxelib: &Haxelib, repo_path: &Path, separator: &str)
         return Ok(());
     }
 
-    // Commit not found locally - fetch from managed remote and retry
+    if options.offline {
+        fetch_from_cached_mirror(haxelib, repo_path, &remote_name, &options.cache())?;
+    } else {
+        fetch_from_remote(haxelib, repo_path, &remote_name, options.proxy.as_deref())?;
+    }
+
+    // Try checkout again after fetch
+    let checkout_retry = std::process::Command::new("git")
+        .args(["-C", path_to_str(repo_path)?, "checkout", target_ref])
+        .status()
+        .context("Failed to execute git checkout after fetch")?;
+
+    if !checkout_retry.success() {
+        return Err(anyhow!(
+            "Commit {} not found even after fetch for {}",
+            target_ref,
+            haxelib.name
+        ));
+    }
+
+    println!("✓ Checked out {} (after fetch)", target_ref);
+    Ok(())
+}
+
+/// Fetch from the managed remote, retrying with --refetch if negotiation fails
+fn fetch_from_remote(
+    haxelib: &Haxelib,
+    repo_path: &Path,
+    remote_name: &str,
+    proxy: Option<&str>,
+) -> Result<()> {
     println!(
         "Commit {} not found locally, fetching from {}...",
-        target_ref, remote_name
+        haxelib.vcs_ref()?,
+        remote_name
     );
 
     let fetch_result = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "fetch", &remote_name])
+        .args(["-C", path_to_str(repo_path)?, "fetch", remote_name])
+        .envs(hmm::proxy::command_env(proxy))
         .status()
         .context("Failed to execute git fetch")?;
 
@@ -365,11 +1626,12 @@ fn smart_checkout_git_ref(haxelib: &Haxelib, repo_path: &Path, separator: &str)
         let refetch_result = std::process::Command::new("git")
             .args([
                 "-C",
-                path_to_str(&repo_path)?,
+                path_to_str(repo_path)?,
                 "fetch",
                 "--refetch",
-                &remote_name,
+                remote_name,
             ])
+            .envs(hmm::proxy::command_env(proxy))
             .status()
             .context("Failed to execute git fetch --refetch")?;
 
@@ -382,35 +1644,62 @@ fn smart_checkout_git_ref(haxelib: &Haxelib, repo_path: &Path, separator: &str)
         }
     }
 
-    // Try checkout again after fetch
-    let checkout_retry = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "checkout", target_ref])
+    Ok(())
+}
+
+/// Fetch branches and tags for `remote_name` straight out of the cached mirror
+fn fetch_from_cached_mirror(
+    haxelib: &Haxelib,
+    repo_path: &Path,
+    remote_name: &str,
+    cache: &Cache,
+) -> Result<()> {
+    println!(
+        "Commit {} not found locally, fetching from the cached mirror...",
+        haxelib.vcs_ref()?
+    );
+
+    let mirror = update_git_mirror(cache, haxelib.url()?, true, None)?;
+    let result = std::process::Command::new("git")
+        .args([
+            "-C",
+            path_to_str(repo_path)?,
+            "fetch",
+            path_to_str(&mirror)?,
+            &format!("+refs/heads/*:refs/remotes/{}/*", remote_name),
+            "+refs/tags/*:refs/tags/*",
+        ])
         .status()
-        .context("Failed to execute git checkout after fetch")?;
+        .context("Failed to execute git fetch")?;
 
-    if !checkout_retry.success() {
+    if !result.success() {
         return Err(anyhow!(
-            "Commit {} not found even after fetch for {}",
-            target_ref,
-            haxelib.name
+            "Git fetch failed for {} from the cached mirror {}",
+            haxelib.name,
+            mirror.display()
         ));
     }
 
-    println!("✓ Checked out {} (after fetch)", target_ref);
     Ok(())
 }
 
 /// Initialize and update submodules recursively
-fn update_git_submodules(repo_path: &Path) -> Result<()> {
+fn update_git_submodules(repo_path: &Path, options: &InstallOptions) -> Result<()> {
+    let mut args = vec![
+        "-C",
+        path_to_str(repo_path)?,
+        "submodule",
+        "update",
+        "--init",
+        "--recursive",
+    ];
+    if options.offline {
+        args.push("--no-fetch");
+    }
+
     let result = std::process::Command::new("git")
-        .args([
-            "-C",
-            path_to_str(&repo_path)?,
-            "submodule",
-            "update",
-            "--init",
-            "--recursive",
-        ])
+        .args(args)
+        .envs(hmm::proxy::command_env(options.proxy.as_deref()))
         .status()
         .context("Failed to execute git submodule update")?;
 
@@ -557,7 +1846,7 @@ fn ensure_git_remote(repo_path: &Path, remote_name: &str, url: &str) -> Result<(
     let check_remote = std::process::Command::new("git")
         .args([
             "-C",
-            path_to_str(&repo_path)?,
+            path_to_str(repo_path)?,
             "remote",
             "get-url",
             remote_name,
@@ -577,7 +1866,7 @@ fn ensure_git_remote(repo_path: &Path, remote_name: &str, url: &str) -> Result<(
             let update_result = std::process::Command::new("git")
                 .args([
                     "-C",
-                    path_to_str(&repo_path)?,
+                    path_to_str(repo_path)?,
                     "remote",
                     "set-url",
                     remote_name,
@@ -597,7 +1886,7 @@ fn ensure_git_remote(repo_path: &Path, remote_name: &str, url: &str) -> Result<(
         let add_result = std::process::Command::new("git")
             .args([
                 "-C",
-                path_to_str(&repo_path)?,
+                path_to_str(repo_path)?,
                 "remote",
                 "add",
                 remote_name,
@@ -624,7 +1913,7 @@ fn rename_origin_remote(repo_path: &Path, new_name: &str) -> Result<()> {
     let check_origin = std::process::Command::new("git")
         .args([
             "-C",
-            path_to_str(&repo_path)?,
+            path_to_str(repo_path)?,
             "remote",
             "get-url",
             "origin",
@@ -638,7 +1927,7 @@ fn rename_origin_remote(repo_path: &Path, new_name: &str) -> Result<()> {
         let rename_result = std::process::Command::new("git")
             .args([
                 "-C",
-                path_to_str(&repo_path)?,
+                path_to_str(repo_path)?,
                 "remote",
                 "rename",
                 "origin",
@@ -658,7 +1947,7 @@ fn rename_origin_remote(repo_path: &Path, new_name: &str) -> Result<()> {
 }
 
 /// Handle a git conflict by prompting user and executing their choice
-fn handle_git_conflict(haxelib_status: &HaxelibStatus, separator: &str) -> Result<()> {
+fn handle_git_conflict(haxelib_status: &HaxelibStatus, options: &InstallOptions) -> Result<()> {
     let haxelib = haxelib_status.lib;
     let repo_path = haxelib.git_repo_path();
 
@@ -668,16 +1957,16 @@ fn handle_git_conflict(haxelib_status: &HaxelibStatus, separator: &str) -> Resul
     match choice {
         ConflictResolution::Stash => {
             git_stash_push(&repo_path, haxelib)?;
-            install_or_update_git_cli(haxelib, separator)?;
+            install_or_update_git_cli(haxelib, options)?;
             git_stash_pop(&repo_path, haxelib)?;
         }
         ConflictResolution::Discard => {
             git_discard_changes(&repo_path, haxelib)?;
-            install_or_update_git_cli(haxelib, separator)?;
+            install_or_update_git_cli(haxelib, options)?;
         }
         ConflictResolution::Commit => {
             git_commit_changes(&repo_path, haxelib)?;
-            install_or_update_git_cli(haxelib, separator)?;
+            install_or_update_git_cli(haxelib, options)?;
         }
         ConflictResolution::Skip => {
             println!("Skipping {}", haxelib.name.yellow());
@@ -699,7 +1988,7 @@ fn git_stash_push(repo_path: &Path, haxelib: &Haxelib) -> Result<()> {
     let result = std::process::Command::new("git")
         .args([
             "-C",
-            path_to_str(&repo_path)?,
+            path_to_str(repo_path)?,
             "stash",
             "push",
             "-m",
@@ -722,7 +2011,7 @@ fn git_stash_pop(repo_path: &Path, haxelib: &Haxelib) -> Result<()> {
     println!("Restoring stashed changes in {}...", haxelib.name);
 
     let result = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "stash", "pop"])
+        .args(["-C", path_to_str(repo_path)?, "stash", "pop"])
         .output()
         .context("Failed to execute git stash pop")?;
 
@@ -765,7 +2054,7 @@ fn git_discard_changes(repo_path: &Path, haxelib: &Haxelib) -> Result<()> {
 
     // Reset tracked files
     let reset_result = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "reset", "--hard", "HEAD"])
+        .args(["-C", path_to_str(repo_path)?, "reset", "--hard", "HEAD"])
         .status()
         .context("Failed to execute git reset")?;
 
@@ -775,7 +2064,7 @@ fn git_discard_changes(repo_path: &Path, haxelib: &Haxelib) -> Result<()> {
 
     // Clean untracked files
     let clean_result = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "clean", "-fd"])
+        .args(["-C", path_to_str(repo_path)?, "clean", "-fd"])
         .status()
         .context("Failed to execute git clean")?;
 
@@ -808,7 +2097,7 @@ fn git_commit_changes(repo_path: &Path, haxelib: &Haxelib) -> Result<()> {
 
     // Stage all changes
     let add_result = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "add", "-A"])
+        .args(["-C", path_to_str(repo_path)?, "add", "-A"])
         .status()
         .context("Failed to execute git add")?;
 
@@ -818,7 +2107,7 @@ fn git_commit_changes(repo_path: &Path, haxelib: &Haxelib) -> Result<()> {
 
     // Commit
     let commit_result = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "commit", "-m", message])
+        .args(["-C", path_to_str(repo_path)?, "commit", "-m", message])
         .output()
         .context("Failed to execute git commit")?;
 
@@ -841,7 +2130,7 @@ fn git_commit_changes(repo_path: &Path, haxelib: &Haxelib) -> Result<()> {
 /// Get a summary of changed files in the git repository
 fn get_git_diff_stat(repo_path: &Path) -> Result<String> {
     let output = std::process::Command::new("git")
-        .args(["-C", path_to_str(&repo_path)?, "diff", "--stat"])
+        .args(["-C", path_to_str(repo_path)?, "diff", "--stat"])
         .output()
         .context("Failed to get git diff stat")?;
 
@@ -956,23 +2245,31 @@ fn prompt_conflict_resolution(
 
 pub fn create_current_file(path: &Path, content: &String) -> Result<()> {
     std::fs::create_dir_all(path)?;
-    let mut current_version_file = File::create(path.join(".current"))?;
+    // written aside and renamed, so `.current` is never seen half written
+    let temp_path = path.join(format!(".current.{}", unique_suffix()));
+    let mut current_version_file = File::create(&temp_path)?;
     write!(current_version_file, "{}", content)?;
+    drop(current_version_file);
+    std::fs::rename(&temp_path, path.join(".current"))?;
     Ok(())
 }
 
-/// If the git dependency specifies a subdirectory (`dir`), set up a dev link so that
-/// `-lib <name>` resolves to `.haxelib/<name>/git/<dir>/`. This mirrors real haxelib,
-/// which sets a dev path to `<versionPath>/<subDir>` for subdirectory git installs.
-pub fn ensure_git_subdir_dev_link(haxelib: &Haxelib) -> Result<()> {
+/// If the git / hg dependency specifies a subdirectory (`dir`), set up a dev link so that
+/// `-lib <name>` resolves to `.haxelib/<name>/<git|hg>/<dir>/`. This mirrors real haxelib,
+/// which sets a dev path to `<versionPath>/<subDir>` for subdirectory vcs installs.
+pub fn ensure_subdir_dev_link(haxelib: &Haxelib) -> Result<()> {
     let subdir = match haxelib.dir.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
         Some(d) => d,
         None => return Ok(()), // no subdir: nothing to do
     };
 
-    let abs_git = std::fs::canonicalize(haxelib.git_repo_path())
-        .with_context(|| format!("Failed to resolve git repo path for {}", haxelib.name))?;
-    let abs_subdir = abs_git.join(subdir);
+    let repo_path = match haxelib.haxelib_type {
+        HaxelibType::Mecurial => haxelib.hg_repo_path(),
+        _ => haxelib.git_repo_path(),
+    };
+    let abs_repo = std::fs::canonicalize(repo_path)
+        .with_context(|| format!("Failed to resolve repo path for {}", haxelib.name))?;
+    let abs_subdir = abs_repo.join(subdir);
 
     if !abs_subdir.exists() {
         println!(
@@ -1050,6 +2347,72 @@ mod tests {
         assert_eq!(resolve_remote_separator(Some("__")), "__");
     }
 
+    #[test]
+    fn test_retry_policy_new() {
+        assert_eq!(
+            RetryPolicy::new(None, None),
+            RetryPolicy {
+                retries: DEFAULT_RETRIES,
+                delay: Duration::from_millis(DEFAULT_RETRY_DELAY_MS),
+            }
+        );
+        assert_eq!(
+            RetryPolicy::new(Some(7), Some(20)),
+            RetryPolicy {
+                retries: 7,
+                delay: Duration::from_millis(20),
+            }
+        );
+    }
+
+    #[test]
+    fn test_retry_backoff_doubles_up_to_the_cap() {
+        let retry = RetryPolicy {
+            retries: 10,
+            delay: Duration::from_millis(500),
+        };
+        assert_eq!(retry.backoff(0), Duration::from_millis(500));
+        assert_eq!(retry.backoff(1), Duration::from_secs(1));
+        assert_eq!(retry.backoff(3), Duration::from_secs(4));
+        assert_eq!(retry.backoff(9), MAX_RETRY_DELAY);
+        assert_eq!(retry.backoff(u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_content_range_start() {
+        assert_eq!(content_range_start("bytes 100-199/200"), Some(100));
+        assert_eq!(content_range_start("bytes 0-9/*"), Some(0));
+        assert_eq!(content_range_start("bytes */200"), None);
+        assert_eq!(content_range_start("items 1-2/3"), None);
+    }
+
+    #[test]
+    fn test_symlink_stays_inside() {
+        assert!(symlink_stays_inside("run", "bin/run.sh"));
+        assert!(symlink_stays_inside("bin/json", "../haxelib.json"));
+        assert!(symlink_stays_inside("a/b/c", "../../d/./e"));
+        assert!(!symlink_stays_inside("run", "../run.sh"));
+        assert!(!symlink_stays_inside("run", "/bin/sh"));
+        assert!(!symlink_stays_inside("run", "C:\\Windows"));
+        assert!(!symlink_stays_inside("a/b", "c/../../.."));
+        assert!(!symlink_stays_inside("a/b", "c/.."));
+        assert!(!symlink_stays_inside("run", ""));
+    }
+
+    #[test]
+    fn test_zip_time() {
+        let time = |y, mo, d, h, mi, s| {
+            zip_time(zip::DateTime::from_date_and_time(y, mo, d, h, mi, s).unwrap())
+                .unwrap()
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        assert_eq!(time(1980, 1, 1, 0, 0, 0), 315532800);
+        assert_eq!(time(2020, 2, 29, 12, 30, 10), 1582979410);
+        assert_eq!(time(2107, 12, 31, 23, 59, 58), 4354819198);
+    }
+
     #[test]
     fn test_resolve_separator_env_and_default() {
         // This test mutates HMM_REMOTE_SEPARATOR. Keep all env-var-reading
@@ -1155,4 +2518,73 @@ mod tests {
             .unwrap();
         assert_eq!(String::from_utf8_lossy(&promisor.stdout).trim(), "true");
     }
+
+    fn haxelib_with_versions() -> HaxelibApi {
+        use crate::hmm::haxe_serializer::Value;
+        use crate::hmm::haxelib_api::mock::{self, object, remoting_response, string};
+
+        let (url, _requests) = mock::serve(|_| {
+            let versions = ["5.1.0", "5.2.0", "5.2.3", "6.0.0"]
+                .iter()
+                .map(|v| object(vec![("name", string(v))]))
+                .collect();
+            remoting_response(object(vec![("versions", Value::Array(versions))]))
+        });
+        HaxelibApi::new(&url)
+    }
+
+    #[test]
+    fn test_resolve_constraints_pins_highest_match() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let json_path = temp.path().join("hmm.json");
+        let deps: Dependancies = serde_json::from_str(
+            r#"{"dependencies": [
+                {"name": "lime", "type": "haxelib", "constraint": "^5.2", "version": "5.1.0", "integrity": "abc"},
+                {"name": "openfl", "type": "haxelib", "constraint": ">=5 <6", "version": "5.2.0"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let deps = resolve_constraints(
+            &haxelib_with_versions(),
+            deps,
+            &[],
+            json_path.clone(),
+            false,
+        )
+        .unwrap();
+        let lime = deps.get_haxelib("lime").unwrap();
+        assert_eq!(lime.version.as_deref(), Some("5.2.3"));
+        assert_eq!(lime.integrity, None);
+        // already within its constraint, so it stays put
+        assert_eq!(
+            deps.get_haxelib("openfl").unwrap().version.as_deref(),
+            Some("5.2.0")
+        );
+        assert_eq!(
+            hmm::json::read_json(&json_path).unwrap().dependencies[0]
+                .version
+                .as_deref(),
+            Some("5.2.3")
+        );
+    }
+
+    #[test]
+    fn test_resolve_constraints_without_a_match() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let deps: Dependancies = serde_json::from_str(
+            r#"{"dependencies": [{"name": "lime", "type": "haxelib", "constraint": "^7"}]}"#,
+        )
+        .unwrap();
+
+        let result = resolve_constraints(
+            &haxelib_with_versions(),
+            deps,
+            &[],
+            temp.path().join("hmm.json"),
+            false,
+        );
+        assert!(result.is_err());
+        assert!(!temp.path().join("hmm.json").exists());
+    }
 }
@@ -0,0 +1,347 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use owo_colors::OwoColorize;
+
+use crate::commands::import_command;
+use crate::hmm::{
+    dependencies::Dependancies,
+    graph::read_haxelib_json,
+    haxelib::{installed_path_for_name, Haxelib, HaxelibType},
+};
+
+/// Reads every `<dir>/<name>.hxml` lix scope file into `deps` and saves hmm.json.
+/// Libraries already in hmm.json are replaced by what lix says
+pub fn import_lix(dir: &Path, deps: Dependancies, json_path: PathBuf) -> Result<()> {
+    let mut files = std::fs::read_dir(dir)
+        .with_context(|| format!("lix scope {} not found", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "hxml"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut imported = Vec::new();
+    for file in files {
+        let Some(name) = file.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let content = std::fs::read_to_string(&file)?;
+        let haxelib = match parse_lix_hxml(name, &content) {
+            Ok(Some(haxelib)) => haxelib,
+            Ok(None) => {
+                println!(
+                    "{}: no `# @install` line or class path, skipping",
+                    file.display().yellow()
+                );
+                continue;
+            }
+            Err(e) => {
+                println!("{}: {}, skipping", file.display().yellow(), e);
+                continue;
+            }
+        };
+        imported.push(haxelib);
+    }
+
+    if imported.is_empty() {
+        println!("No lix libraries found in {}", dir.display());
+        return Ok(());
+    }
+    import_command::merge_into_hmm_json(deps, imported, json_path)
+}
+
+/// Turns the lix scope file of library `name` into a hmm.json entry.
+/// Libraries lix downloads are read from the `# @install` line, `lix dev` libraries from `-cp`.
+/// Returns None for files that have neither
+pub fn parse_lix_hxml(name: &str, content: &str) -> Result<Option<Haxelib>> {
+    let mut haxelib = Haxelib {
+        name: name.to_string(),
+        haxelib_type: HaxelibType::Haxelib,
+        vcs_ref: None,
+        dir: None,
+        path: None,
+        url: None,
+        version: None,
+        constraint: None,
+        integrity: None,
+    };
+
+    let install = content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("# @install:"));
+    let Some(install) = install else {
+        let class_path = content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("-cp "))
+            .map(str::trim)
+            .filter(|cp| !cp.contains("${HAXE_LIBCACHE}"));
+        return Ok(class_path.map(|cp| {
+            haxelib.haxelib_type = HaxelibType::Dev;
+            haxelib.path = Some(cp.to_string());
+            haxelib
+        }));
+    };
+
+    // lix --silent download "haxelib:/flixel#5.6.1" into flixel/5.6.1/haxelib
+    let source = match install.split_once('"') {
+        Some((_, rest)) => rest.split('"').next(),
+        None => install
+            .split_whitespace()
+            .skip_while(|arg| *arg != "download")
+            .nth(1),
+    }
+    .ok_or_else(|| anyhow!("can't find the download url in `{}`", install.trim()))?;
+
+    let (source, vcs_ref) = match source.rsplit_once('#') {
+        Some((source, vcs_ref)) => (source, Some(vcs_ref.to_string())),
+        None => (source, None),
+    };
+
+    if let Some(lib) = source.strip_prefix("haxelib:") {
+        if lib.trim_start_matches('/').is_empty() {
+            return Err(anyhow!("missing haxelib name in {}", source));
+        }
+        haxelib.version = vcs_ref;
+        return Ok(Some(haxelib));
+    }
+
+    let url = if let Some(repo) = strip_host(source, &["gh:", "github:"], "github.com") {
+        format!("https://github.com/{}", repo)
+    } else if let Some(repo) = strip_host(source, &["gitlab:"], "gitlab.com") {
+        format!("https://gitlab.com/{}", repo)
+    } else if let Some(url) = source.strip_prefix("git:") {
+        url.to_string()
+    } else {
+        return Err(anyhow!("unsupported lix source {}", source));
+    };
+    haxelib.haxelib_type = HaxelibType::Git;
+    haxelib.url = Some(url);
+    haxelib.vcs_ref = vcs_ref;
+    Ok(Some(haxelib))
+}
+
+/// `gh://github.com/owner/repo` and `github:owner/repo` both give `owner/repo`
+fn strip_host<'a>(source: &'a str, schemes: &[&str], host: &str) -> Option<&'a str> {
+    let rest = schemes
+        .iter()
+        .find_map(|scheme| source.strip_prefix(scheme))?
+        .trim_start_matches('/');
+    Some(
+        rest.strip_prefix(host)
+            .map(|r| r.trim_start_matches('/'))
+            .unwrap_or(rest),
+    )
+}
+
+/// Writes a lix scope file to `<dir>/<name>.hxml` for every dependency in hmm.json.
+/// Class paths and `-lib` lines come from the installed haxelib.json, so install first.
+/// Doesn't touch `.haxerc`
+pub fn export_lix(deps: &Dependancies, dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for haxelib in deps.dependencies.iter() {
+        let hxml = match lix_hxml(haxelib) {
+            Ok(hxml) => hxml,
+            Err(e) => {
+                println!("{}: {}, skipping", haxelib.name.yellow(), e);
+                continue;
+            }
+        };
+        let path = dir.join(format!("{}.hxml", haxelib.name));
+        std::fs::write(&path, hxml)?;
+        println!("{} {}", "Wrote".green(), path.display());
+    }
+    Ok(())
+}
+
+/// The lix scope file of `haxelib`, the inverse of `parse_lix_hxml`
+pub fn lix_hxml(haxelib: &Haxelib) -> Result<String> {
+    let installed = installed_path_for_name(&haxelib.name)
+        .and_then(|path| read_haxelib_json(&path).ok().flatten())
+        .unwrap_or_default();
+    let class_path = installed.class_path.as_deref().unwrap_or("");
+
+    let mut hxml = String::new();
+    for dep in installed.dependencies.keys() {
+        hxml.push_str(&format!("-lib {}\n", dep));
+    }
+
+    let version = match haxelib.haxelib_type {
+        HaxelibType::Haxelib => {
+            let version = haxelib
+                .version
+                .as_deref()
+                .ok_or_else(|| anyhow!("not locked to a version"))?;
+            let into = format!("{}/{}/haxelib", haxelib.name, version);
+            hxml.push_str(&format!(
+                "# @install: lix --silent download \"haxelib:/{}#{}\" into {}\n",
+                haxelib.name, version, into
+            ));
+            hxml.push_str(&format!(
+                "-cp {}\n",
+                join_class_path(&format!("${{HAXE_LIBCACHE}}/{}", into), None, class_path)
+            ));
+            version.to_string()
+        }
+        HaxelibType::Git => {
+            let url = haxelib.url()?;
+            let vcs_ref = haxelib
+                .vcs_ref
+                .as_deref()
+                .ok_or_else(|| anyhow!("not locked to a ref"))?;
+            let version = installed.version.as_deref().unwrap_or("0.0.0");
+            let (source, kind) = match (
+                url.strip_prefix("https://github.com/"),
+                url.strip_prefix("https://gitlab.com/"),
+            ) {
+                (Some(repo), _) => (
+                    format!("gh://github.com/{}", repo.trim_end_matches(".git")),
+                    "github",
+                ),
+                (_, Some(repo)) => (
+                    format!("gitlab://gitlab.com/{}", repo.trim_end_matches(".git")),
+                    "gitlab",
+                ),
+                _ => (format!("git:{}", url), "git"),
+            };
+            let into = format!("{}/{}/{}/{}", haxelib.name, version, kind, vcs_ref);
+            hxml.push_str(&format!(
+                "# @install: lix --silent download \"{}#{}\" into {}\n",
+                source, vcs_ref, into
+            ));
+            hxml.push_str(&format!(
+                "-cp {}\n",
+                join_class_path(
+                    &format!("${{HAXE_LIBCACHE}}/{}", into),
+                    haxelib.dir.as_deref(),
+                    class_path
+                )
+            ));
+            version.to_string()
+        }
+        HaxelibType::Dev => {
+            let path = haxelib
+                .path
+                .as_deref()
+                .ok_or_else(|| anyhow!("'path' field is required for dev type"))?;
+            hxml.push_str(&format!(
+                "-cp {}\n",
+                join_class_path(path, None, class_path)
+            ));
+            installed.version.unwrap_or_else(|| "0.0.0".to_string())
+        }
+        HaxelibType::Mecurial => return Err(anyhow!("lix can't install Mercurial libraries")),
+    };
+    hxml.push_str(&format!("-D {}={}\n", haxelib.name, version));
+    Ok(hxml)
+}
+
+fn join_class_path(root: &str, dir: Option<&str>, class_path: &str) -> String {
+    let mut path = root.trim_end_matches('/').to_string();
+    for part in [dir.unwrap_or(""), class_path] {
+        let part = part.trim_matches('/');
+        if !part.is_empty() {
+            path.push('/');
+            path.push_str(part);
+        }
+    }
+    path.push('/');
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn haxelib(name: &str, haxelib_type: HaxelibType) -> Haxelib {
+        Haxelib {
+            name: name.to_string(),
+            haxelib_type,
+            vcs_ref: None,
+            dir: None,
+            path: None,
+            url: None,
+            version: None,
+            constraint: None,
+            integrity: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_lix_haxelib() {
+        let content = "-lib lime\n# @install: lix --silent download \"haxelib:/flixel#5.6.1\" into flixel/5.6.1/haxelib\n-cp ${HAXE_LIBCACHE}/flixel/5.6.1/haxelib/\n-D flixel=5.6.1\n";
+        let lib = parse_lix_hxml("flixel", content).unwrap().unwrap();
+        assert_eq!(lib.haxelib_type, HaxelibType::Haxelib);
+        assert_eq!(lib.version.as_deref(), Some("5.6.1"));
+    }
+
+    #[test]
+    fn test_parse_lix_github() {
+        let content = "# @install: lix --silent download \"gh://github.com/HaxeFoundation/hxcpp#a1b2c3\" into hxcpp/4.3.2/github/a1b2c3\n";
+        let lib = parse_lix_hxml("hxcpp", content).unwrap().unwrap();
+        assert_eq!(lib.haxelib_type, HaxelibType::Git);
+        assert_eq!(
+            lib.url.as_deref(),
+            Some("https://github.com/HaxeFoundation/hxcpp")
+        );
+        assert_eq!(lib.vcs_ref.as_deref(), Some("a1b2c3"));
+
+        let content = "# @install: lix download \"gitlab:owner/repo#main\"\n";
+        let lib = parse_lix_hxml("repo", content).unwrap().unwrap();
+        assert_eq!(lib.url.as_deref(), Some("https://gitlab.com/owner/repo"));
+    }
+
+    #[test]
+    fn test_parse_lix_dev_and_unsupported() {
+        let lib = parse_lix_hxml("mylib", "-cp /home/me/mylib/src\n-D mylib=1.0.0\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(lib.haxelib_type, HaxelibType::Dev);
+        assert_eq!(lib.path.as_deref(), Some("/home/me/mylib/src"));
+
+        assert!(parse_lix_hxml("empty", "-D empty\n").unwrap().is_none());
+        assert!(parse_lix_hxml(
+            "zip",
+            "# @install: lix download \"https://example.com/lib.zip\"\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_lix_hxml_round_trips() {
+        let mut flixel = haxelib("flixel", HaxelibType::Haxelib);
+        flixel.version = Some("5.6.1".to_string());
+        let mut hxcpp = haxelib("hxcpp", HaxelibType::Git);
+        hxcpp.url = Some("https://github.com/HaxeFoundation/hxcpp".to_string());
+        hxcpp.vcs_ref = Some("a1b2c3".to_string());
+        let mut other = haxelib("other", HaxelibType::Git);
+        other.url = Some("https://git.example.com/other.git".to_string());
+        other.vcs_ref = Some("v1".to_string());
+
+        for lib in [flixel, hxcpp, other] {
+            let hxml = lix_hxml(&lib).unwrap();
+            let parsed = parse_lix_hxml(&lib.name, &hxml).unwrap().unwrap();
+            assert_eq!(parsed.haxelib_type, lib.haxelib_type);
+            assert_eq!(parsed.version, lib.version);
+            assert_eq!(parsed.vcs_ref, lib.vcs_ref);
+            assert_eq!(parsed.url, lib.url);
+        }
+    }
+
+    #[test]
+    fn test_lix_hxml_requires_locked_versions() {
+        assert!(lix_hxml(&haxelib("lime", HaxelibType::Haxelib)).is_err());
+        assert!(lix_hxml(&haxelib("hgdep", HaxelibType::Mecurial)).is_err());
+    }
+
+    #[test]
+    fn test_join_class_path() {
+        assert_eq!(
+            join_class_path("${HAXE_LIBCACHE}/a/1/haxelib", None, ""),
+            "${HAXE_LIBCACHE}/a/1/haxelib/"
+        );
+        assert_eq!(
+            join_class_path("root/", Some("lib"), "src/"),
+            "root/lib/src/"
+        );
+    }
+}
@@ -4,16 +4,22 @@ use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
 use owo_colors::OwoColorize;
+use serde_json::{json, Value};
 
+use crate::commands::install_command::{self, InstallOptions};
+use crate::hmm::cache::sha256_file;
 use crate::hmm::dependencies::Dependancies;
 use crate::hmm::haxelib::{Haxelib, HaxelibType};
+use crate::hmm::hg;
 use crate::hmm::json;
+use crate::OutputFormat;
 
 pub fn lock_dependencies(
     deps: &Dependancies,
     libs: &[String],
     json_path: PathBuf,
     long_id: bool,
+    options: &InstallOptions,
 ) -> Result<()> {
     let mut updated_deps = deps.clone();
 
@@ -31,7 +37,7 @@ pub fn lock_dependencies(
             continue;
         }
 
-        match lock_dependency(lib, long_id) {
+        match lock_dependency(lib, long_id, options) {
             Ok(LockResult::Locked(version)) => {
                 println!(
                     "{} {} locked to {}",
@@ -94,29 +100,39 @@ enum LockResult {
     AlreadyLocked(String),
 }
 
-fn lock_dependency(lib: &mut Haxelib, long_id: bool) -> Result<LockResult> {
+fn lock_dependency(
+    lib: &mut Haxelib,
+    long_id: bool,
+    options: &InstallOptions,
+) -> Result<LockResult> {
     match lib.haxelib_type {
-        HaxelibType::Haxelib => lock_haxelib_dependency(lib),
+        HaxelibType::Haxelib => lock_haxelib_dependency(lib, options),
         HaxelibType::Git => lock_git_dependency(lib, long_id),
         HaxelibType::Dev => Ok(LockResult::Skipped(
             "dev dependencies are already locked by path".to_string(),
         )),
-        HaxelibType::Mecurial => Ok(LockResult::Skipped(
-            "mercurial not yet supported".to_string(),
-        )),
+        HaxelibType::Mecurial => lock_hg_dependency(lib, long_id),
     }
 }
 
-fn lock_haxelib_dependency(lib: &mut Haxelib) -> Result<LockResult> {
-    // Check if already locked
-    if lib.version.is_some() {
-        return Ok(LockResult::AlreadyLocked(String::from(lib.version()?)));
-    }
-
+fn lock_haxelib_dependency(lib: &mut Haxelib, options: &InstallOptions) -> Result<LockResult> {
     // Read the .current file to get installed version
     let lib_path = lib.lib_dir_path();
     let current_file = lib_path.join(".current");
 
+    // Check if already locked
+    if let Some(version) = lib.version.clone() {
+        // Only hash what is actually installed, so the integrity matches the version
+        let installed = std::fs::read_to_string(&current_file).ok();
+        if lib.integrity.is_none() && installed.as_deref() == Some(version.as_str()) {
+            record_haxelib_integrity(lib, options);
+            if lib.integrity.is_some() {
+                return Ok(LockResult::Locked(version));
+            }
+        }
+        return Ok(LockResult::AlreadyLocked(version));
+    }
+
     if !current_file.exists() {
         return Err(anyhow!(
             "Library not installed (no .current file found). Run `hmm install` first."
@@ -128,10 +144,26 @@ fn lock_haxelib_dependency(lib: &mut Haxelib) -> Result<LockResult> {
 
     // Update the library with the locked version
     lib.version = Some(current_version.clone());
+    record_haxelib_integrity(lib, options);
 
     Ok(LockResult::Locked(current_version))
 }
 
+/// Sets `integrity` to the sha256 of the haxelib zip, downloading it into the cache if needed.
+/// Failing to get the zip only warns, the version is still locked.
+fn record_haxelib_integrity(lib: &mut Haxelib, options: &InstallOptions) {
+    let hash = install_command::fetch_haxelib_zip(lib, options).and_then(|zip| sha256_file(&zip));
+
+    match hash {
+        Ok(hash) => lib.integrity = Some(hash),
+        Err(e) => println!(
+            "{} {}",
+            format!("{}: could not record integrity hash:", lib.name).yellow(),
+            e.to_string().yellow()
+        ),
+    }
+}
+
 fn lock_git_dependency(lib: &mut Haxelib, long_id: bool) -> Result<LockResult> {
     let git_path = lib.git_repo_path();
 
@@ -151,20 +183,48 @@ fn lock_git_dependency(lib: &mut Haxelib, long_id: bool) -> Result<LockResult> {
         head_commit.id().shorten_or_id().to_string()
     };
 
+    let tree_hash = head_commit.tree_id()?.to_string();
+
     // Check if already locked to this exact commit
     if let Some(ref current_ref) = lib.vcs_ref {
-        if current_ref == &commit_sha {
+        if current_ref == &commit_sha && lib.integrity.is_some() {
             return Ok(LockResult::AlreadyLocked(commit_sha));
         }
     }
 
-    // Update the ref to the commit SHA
+    // Update the ref to the commit SHA, and record its tree for integrity checks
     lib.vcs_ref = Some(commit_sha.clone());
+    lib.integrity = Some(tree_hash);
 
     Ok(LockResult::Locked(commit_sha))
 }
 
-pub fn check_locked(deps: &Dependancies) -> Result<()> {
+fn lock_hg_dependency(lib: &mut Haxelib, long_id: bool) -> Result<LockResult> {
+    let hg_path = lib.hg_repo_path();
+
+    if !hg_path.exists() {
+        return Err(anyhow!(
+            "Mercurial repository not cloned. Run `hmm install` first."
+        ));
+    }
+
+    let changeset = hg::current_changeset(&hg_path, long_id)?;
+
+    // Check if already locked to this exact changeset
+    if lib.vcs_ref.as_deref() == Some(changeset.as_str()) {
+        return Ok(LockResult::AlreadyLocked(changeset));
+    }
+
+    lib.vcs_ref = Some(changeset.clone());
+
+    Ok(LockResult::Locked(changeset))
+}
+
+pub fn check_locked(deps: &Dependancies, format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        return print_locked_json(deps);
+    }
+
     let mut unlocked_libs = Vec::new();
     let mut locked_count = 0;
 
@@ -212,6 +272,48 @@ pub fn check_locked(deps: &Dependancies) -> Result<()> {
     Ok(())
 }
 
+/// `lock check --format json`: `{ dependencies: [{ name, type, status, reason }], locked, total }`
+fn print_locked_json(deps: &Dependancies) -> Result<()> {
+    let statuses: Vec<(&Haxelib, LockStatus)> = deps
+        .dependencies
+        .iter()
+        .map(|lib| (lib, is_locked(lib)))
+        .collect();
+
+    let unlocked_count = statuses
+        .iter()
+        .filter(|(_, status)| matches!(status, LockStatus::NotLocked(_)))
+        .count();
+    let dependencies: Vec<Value> = statuses
+        .iter()
+        .map(|(lib, status)| {
+            let (status, reason) = match status {
+                LockStatus::Locked => ("locked", None),
+                LockStatus::NotLocked(reason) => ("not_locked", Some(reason)),
+                LockStatus::NotApplicable => ("not_applicable", None),
+            };
+            json!({
+                "name": lib.name,
+                "type": lib.haxelib_type,
+                "status": status,
+                "reason": reason,
+            })
+        })
+        .collect();
+
+    let report = json!({
+        "dependencies": dependencies,
+        "locked": statuses.len() - unlocked_count,
+        "total": statuses.len(),
+    });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if unlocked_count > 0 {
+        return Err(anyhow!("{} dependencies are not locked", unlocked_count));
+    }
+    Ok(())
+}
+
 enum LockStatus {
     Locked,
     NotLocked(String),
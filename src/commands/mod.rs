@@ -1,12 +1,22 @@
 pub mod add_command;
+pub mod cache_command;
 pub mod check_command;
 pub mod clean_command;
+pub mod config_command;
 pub mod dev_command;
+pub mod fromhxml_command;
 pub mod git_command;
 pub mod haxelib_command;
+pub mod import_command;
+pub mod info_command;
 pub mod init_command;
 pub mod install_command;
+pub mod lix_command;
 pub mod lock_command;
+pub mod outdated_command;
 pub mod remove_command;
+pub mod search_command;
 pub mod tohxml_command;
+pub mod tree_command;
+pub mod update_command;
 pub mod upgrade_command;
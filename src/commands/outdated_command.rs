@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use owo_colors::OwoColorize;
+use serde_json::{json, Value};
+
+use crate::hmm::dependencies::Dependancies;
+use crate::hmm::haxelib::{git_repo_path_for_name, lib_dir_path_for_name, Haxelib, HaxelibType};
+use crate::hmm::haxelib_api::HaxelibApi;
+use crate::hmm::proxy;
+use crate::OutputFormat;
+
+/// What `git ls-remote` reports about a repository
+#[derive(Debug, Default, PartialEq)]
+pub struct RemoteRefs {
+    pub head: Option<String>,
+    /// branch name -> commit
+    pub branches: BTreeMap<String, String>,
+    /// tag name -> commit, annotated tags already peeled
+    pub tags: BTreeMap<String, String>,
+}
+
+impl RemoteRefs {
+    /// The highest tag that parses as semver, with or without a leading `v`
+    pub fn newest_tag(&self) -> Option<&str> {
+        self.tags
+            .keys()
+            .filter_map(|tag| parse_version(tag).map(|version| (version, tag)))
+            .max()
+            .map(|(_, tag)| tag.as_str())
+    }
+}
+
+/// Lists the branches and tags of `url` without cloning it, through `proxy` if any
+pub fn ls_remote(url: &str, proxy: Option<&str>) -> Result<RemoteRefs> {
+    let output = Command::new("git")
+        .args(["ls-remote", url])
+        .envs(proxy::command_env(proxy))
+        .output()
+        .context("Failed to execute git ls-remote")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git ls-remote {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_ls_remote(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_ls_remote(output: &str) -> RemoteRefs {
+    let mut refs = RemoteRefs::default();
+    for line in output.lines() {
+        let Some((commit, name)) = line.split_once('\t') else {
+            continue;
+        };
+        let commit = commit.trim().to_string();
+        if name == "HEAD" {
+            refs.head = Some(commit);
+        } else if let Some(branch) = name.strip_prefix("refs/heads/") {
+            refs.branches.insert(branch.to_string(), commit);
+        } else if let Some(tag) = name.strip_prefix("refs/tags/") {
+            match tag.strip_suffix("^{}") {
+                // the commit an annotated tag points to, rather than the tag object
+                Some(tag) => {
+                    refs.tags.insert(tag.to_string(), commit);
+                }
+                None => {
+                    refs.tags.entry(tag.to_string()).or_insert(commit);
+                }
+            }
+        }
+    }
+    refs
+}
+
+/// Parses `1.2.3` or `v1.2.3`
+pub fn parse_version(version: &str) -> Option<semver::Version> {
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+/// Whether `candidate` is a later release than `than`, comparing as semver when both parse
+pub fn is_newer(candidate: &str, than: &str) -> bool {
+    match (parse_version(candidate), parse_version(than)) {
+        (Some(candidate), Some(than)) => candidate > than,
+        _ => candidate != than,
+    }
+}
+
+/// One row of the `outdated` report
+#[derive(Debug, PartialEq)]
+pub struct OutdatedStatus {
+    pub name: String,
+    pub haxelib_type: HaxelibType,
+    /// What's installed in `.haxelib/`
+    pub current: Option<String>,
+    /// What hmm.json pins
+    pub wanted: Option<String>,
+    /// The newest release, branch tip or tag
+    pub latest: Option<String>,
+    pub outdated: bool,
+}
+
+pub fn outdated(
+    api: &HaxelibApi,
+    deps: &Dependancies,
+    names: &[String],
+    format: OutputFormat,
+    proxy: Option<&str>,
+) -> Result<()> {
+    let mut statuses = Vec::new();
+    for lib in deps.filter_by_names(names) {
+        let status = match lib.haxelib_type {
+            HaxelibType::Haxelib => haxelib_status(api, lib),
+            HaxelibType::Git => git_status(lib, proxy),
+            // dev libraries aren't pinned, and Mercurial has no cheap way to list remote heads
+            HaxelibType::Dev | HaxelibType::Mecurial => continue,
+        };
+        match status {
+            Ok(status) => statuses.push(status),
+            Err(e) => {
+                eprintln!("{}: {}", lib.name.yellow(), e);
+                statuses.push(OutdatedStatus {
+                    name: lib.name.clone(),
+                    haxelib_type: lib.haxelib_type.clone(),
+                    current: None,
+                    wanted: lib.version.clone().or_else(|| lib.vcs_ref.clone()),
+                    latest: None,
+                    outdated: false,
+                });
+            }
+        }
+    }
+
+    match format {
+        OutputFormat::Text => print_table(&statuses),
+        OutputFormat::Json => {
+            let dependencies: Vec<Value> = statuses.iter().map(status_json).collect();
+            let count = statuses.iter().filter(|s| s.outdated).count();
+            let report = json!({
+                "dependencies": dependencies,
+                "outdated": count,
+                "total": statuses.len(),
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+    Ok(())
+}
+
+/// The version in `.haxelib/<name>/.current`, unless it's a vcs checkout
+fn installed_haxelib_version(name: &str) -> Option<String> {
+    let current = std::fs::read_to_string(lib_dir_path_for_name(name).join(".current")).ok()?;
+    let current = current.trim();
+    (!matches!(current, "git" | "hg" | "")).then(|| current.to_string())
+}
+
+/// The commit checked out in `.haxelib/<name>/git`
+pub fn installed_git_commit(name: &str) -> Option<String> {
+    let repo = git_repo_path_for_name(name);
+    if !repo.join(".git").exists() {
+        return None;
+    }
+    let output = Command::new("git")
+        .args(["-C", repo.to_str()?, "rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn haxelib_status(api: &HaxelibApi, lib: &Haxelib) -> Result<OutdatedStatus> {
+    let latest = api.get_latest_version(&lib.name)?;
+    let current = installed_haxelib_version(&lib.name);
+    // without a pin, whatever is installed is what we have
+    let outdated = match lib.version.as_deref().or(current.as_deref()) {
+        Some(wanted) => is_newer(&latest, wanted),
+        None => false,
+    };
+    Ok(OutdatedStatus {
+        name: lib.name.clone(),
+        haxelib_type: HaxelibType::Haxelib,
+        current,
+        wanted: lib.version.clone(),
+        latest: Some(latest),
+        outdated,
+    })
+}
+
+fn git_status(lib: &Haxelib, proxy: Option<&str>) -> Result<OutdatedStatus> {
+    let url = lib.url()?;
+    let refs = ls_remote(url, proxy)?;
+    let current = installed_git_commit(&lib.name);
+    let head = refs
+        .head
+        .clone()
+        .ok_or_else(|| anyhow!("{} has no HEAD", url))?;
+
+    let (latest, outdated) = match lib.vcs_ref.as_deref() {
+        // a branch floats, so only the checkout can be behind
+        Some(r) if refs.branches.contains_key(r) => {
+            let tip = &refs.branches[r];
+            (short(tip), current.as_ref().is_some_and(|c| c != tip))
+        }
+        Some(r) if refs.tags.contains_key(r) => match refs.newest_tag() {
+            Some(newest) => (newest.to_string(), is_newer(newest, r)),
+            None => (r.to_string(), false),
+        },
+        // a commit, most likely from `hmm-rs lock`
+        Some(r) => (short(&head), !head.starts_with(r)),
+        None => (short(&head), current.as_ref().is_some_and(|c| *c != head)),
+    };
+    Ok(OutdatedStatus {
+        name: lib.name.clone(),
+        haxelib_type: HaxelibType::Git,
+        current: current.as_deref().map(short),
+        wanted: lib.vcs_ref.clone(),
+        latest: Some(latest),
+        outdated,
+    })
+}
+
+fn short(commit: &str) -> String {
+    commit.chars().take(7).collect()
+}
+
+fn status_json(status: &OutdatedStatus) -> Value {
+    json!({
+        "name": status.name,
+        "type": status.haxelib_type,
+        "current": status.current,
+        "wanted": status.wanted,
+        "latest": status.latest,
+        "outdated": status.outdated,
+    })
+}
+
+fn print_table(statuses: &[OutdatedStatus]) {
+    if statuses.is_empty() {
+        println!("No haxelib or git dependencies to check");
+        return;
+    }
+
+    let cell = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let rows: Vec<[String; 5]> = statuses
+        .iter()
+        .map(|s| {
+            let haxelib_type = match s.haxelib_type {
+                HaxelibType::Git => "git",
+                _ => "haxelib",
+            };
+            [
+                s.name.clone(),
+                haxelib_type.to_string(),
+                cell(&s.current),
+                cell(&s.wanted),
+                cell(&s.latest),
+            ]
+        })
+        .collect();
+    let header = ["Name", "Type", "Current", "Wanted", "Latest"].map(str::to_string);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in rows.iter() {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    // pad before coloring, color codes would throw the widths off
+    let pad = |row: &[String; 5]| -> Vec<String> {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect()
+    };
+
+    println!("{}", pad(&header).join("  ").trim_end().bold());
+    for (status, row) in statuses.iter().zip(rows.iter()) {
+        let mut cells = pad(row);
+        cells[4] = match (status.outdated, &status.latest) {
+            (true, _) => cells[4].yellow().to_string(),
+            (false, Some(_)) => cells[4].green().to_string(),
+            (false, None) => cells[4].red().to_string(),
+        };
+        println!("{}", cells.join("  ").trim_end());
+    }
+
+    let count = statuses.iter().filter(|s| s.outdated).count();
+    match count {
+        0 => println!("All dependencies are up to date"),
+        n => println!("{} / {} dependencies are outdated", n, statuses.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ls_remote() {
+        let output = "aaaa\tHEAD\nbbbb\trefs/heads/main\ncccc\trefs/tags/v1.0.0\ndddd\trefs/tags/v1.0.0^{}\neeee\trefs/tags/2.0.0\nffff\trefs/pull/1/head\n";
+        let refs = parse_ls_remote(output);
+        assert_eq!(refs.head.as_deref(), Some("aaaa"));
+        assert_eq!(refs.branches["main"], "bbbb");
+        assert_eq!(refs.tags["v1.0.0"], "dddd");
+        assert_eq!(refs.tags.len(), 2);
+    }
+
+    #[test]
+    fn test_newest_tag() {
+        let refs =
+            parse_ls_remote("a\trefs/tags/v1.9.0\nb\trefs/tags/v1.10.0\nc\trefs/tags/nightly\n");
+        assert_eq!(refs.newest_tag(), Some("v1.10.0"));
+        assert_eq!(RemoteRefs::default().newest_tag(), None);
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("5.10.0", "5.9.0"));
+        assert!(!is_newer("5.9.0", "5.9.0"));
+        assert!(!is_newer("5.9.0", "6.0.0-rc.1"));
+        // not semver, so anything different counts
+        assert!(is_newer("nightly", "stable"));
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use serde_json::{json, Value};
+
+use crate::hmm::haxelib_api::{HaxelibApi, ProjectInfos};
+use crate::OutputFormat;
+
+type Found = (String, Option<ProjectInfos>);
+
+/// Searches lib.haxe.org for `query`, and prints the first `limit` matches with their
+/// latest version, description and download count
+pub fn search(api: &HaxelibApi, query: &str, limit: usize, format: OutputFormat) -> Result<()> {
+    let (found, total) = find(api, query, limit)?;
+    match format {
+        OutputFormat::Text => print_results(query, &found, total),
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&results_json(&found, total))?
+            )
+        }
+    }
+    Ok(())
+}
+
+/// The first `limit` matches, and how many there are in total
+fn find(api: &HaxelibApi, query: &str, limit: usize) -> Result<(Vec<Found>, usize)> {
+    let results = api.search(query)?;
+    let total = results.len();
+
+    // search only returns names, the rest takes an `infos` call per project
+    let found = results
+        .into_iter()
+        .take(limit)
+        .map(|result| {
+            let infos = api.infos(&result.name);
+            if let Err(e) = &infos {
+                eprintln!("{}: {}", result.name.yellow(), e);
+            }
+            (result.name, infos.ok())
+        })
+        .collect();
+    Ok((found, total))
+}
+
+fn results_json(found: &[Found], total: usize) -> Value {
+    let results = found
+        .iter()
+        .map(|(name, infos)| {
+            json!({
+                "name": name,
+                "version": infos.as_ref().map(|i| &i.curversion),
+                "description": infos.as_ref().map(|i| &i.desc),
+                "downloads": infos.as_ref().map(|i| i.downloads),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "results": results, "total": total })
+}
+
+fn print_results(query: &str, found: &[Found], total: usize) {
+    if found.is_empty() {
+        println!("No haxelibs found for {}", query.bold());
+        return;
+    }
+
+    for (name, infos) in found {
+        let Some(infos) = infos else {
+            println!("{}", name.green().bold());
+            continue;
+        };
+        println!(
+            "{} {} {}",
+            name.green().bold(),
+            infos.curversion,
+            format!("({} downloads)", infos.downloads).dimmed()
+        );
+        if !infos.desc.is_empty() {
+            println!("    {}", infos.desc);
+        }
+    }
+    if total > found.len() {
+        println!(
+            "Showing {} of {} results, use --limit to see more",
+            found.len(),
+            total
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hmm::haxe_serializer;
+    use crate::hmm::haxelib_api::mock::{self, object, remoting_response, string};
+
+    fn mock_haxelib() -> HaxelibApi {
+        let (url, _requests) = mock::serve(|request| {
+            if mock::calls(request, "search") {
+                return "hxrloy2:idi1y4:namey6:flixelgoR0i2R1y9:flixel-uigh".to_string();
+            }
+            let name = if request.contains("flixel-ui") {
+                "flixel-ui"
+            } else {
+                "flixel"
+            };
+            remoting_response(object(vec![
+                ("name", string(name)),
+                ("desc", string("A description")),
+                ("curversion", string("1.0.0")),
+                ("downloads", haxe_serializer::Value::Int(42)),
+            ]))
+        });
+        HaxelibApi::new(&url)
+    }
+
+    #[test]
+    fn test_find_fetches_infos() {
+        let (found, total) = find(&mock_haxelib(), "flixel", 10).unwrap();
+        assert_eq!(total, 2);
+        let names: Vec<_> = found
+            .iter()
+            .map(|(name, infos)| (name.as_str(), infos.as_ref().unwrap().name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("flixel", "flixel"), ("flixel-ui", "flixel-ui")]
+        );
+    }
+
+    #[test]
+    fn test_find_limit() {
+        let (found, total) = find(&mock_haxelib(), "flixel", 1).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn test_results_json() {
+        let (found, total) = find(&mock_haxelib(), "flixel", 1).unwrap();
+        assert_eq!(
+            results_json(&found, total),
+            json!({
+                "results": [
+                    {"name": "flixel", "version": "1.0.0", "description": "A description", "downloads": 42}
+                ],
+                "total": 2
+            })
+        );
+    }
+}
@@ -11,8 +11,12 @@ pub fn dump_to_hxml(deps: &Dependancies, hxml_out: Option<PathBuf>) -> Result<()
         lib_string.push_str(haxelib.name.as_str());
 
         match haxelib.haxelib_type {
-            HaxelibType::Git => {
-                lib_string.push_str(format!(":git:{}", &haxelib.url()?).as_str());
+            HaxelibType::Git | HaxelibType::Mecurial => {
+                let vcs = match haxelib.haxelib_type {
+                    HaxelibType::Mecurial => "hg",
+                    _ => "git",
+                };
+                lib_string.push_str(format!(":{}:{}", vcs, &haxelib.url()?).as_str());
                 if let Some(r) = &haxelib.vcs_ref {
                     lib_string.push_str(format!("#{}", r).as_str())
                 }
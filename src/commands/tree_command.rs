@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+use serde_json::{json, Value};
+
+use crate::hmm::dependencies::Dependancies;
+use crate::hmm::graph::{DependencyGraph, GraphNode, Requirement};
+
+/// How `tree` and `why` print the dependency graph
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum GraphFormat {
+    Text,
+    Json,
+    /// Graphviz DOT, e.g. `hmm-rs tree -o dot | dot -Tsvg > deps.svg`
+    Dot,
+}
+
+pub fn print_tree(deps: &Dependancies, format: GraphFormat) -> Result<()> {
+    let graph = DependencyGraph::build(deps);
+    graph.warn_unreadable();
+
+    match format {
+        GraphFormat::Text => {
+            let mut expanded = HashSet::new();
+            for root in graph.roots.iter() {
+                println!("{}", node_label(deps, &graph, root).bold());
+                print_children(
+                    deps,
+                    &graph,
+                    root,
+                    "",
+                    &mut vec![root.as_str()],
+                    &mut expanded,
+                );
+            }
+        }
+        GraphFormat::Json => {
+            let mut expanded = HashSet::new();
+            let roots: Vec<Value> = graph
+                .roots
+                .iter()
+                .map(|root| {
+                    tree_json(
+                        deps,
+                        &graph,
+                        root,
+                        None,
+                        &mut vec![root.as_str()],
+                        &mut expanded,
+                    )
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&roots)?);
+        }
+        GraphFormat::Dot => {
+            let edges = graph
+                .nodes
+                .values()
+                .flat_map(|node| {
+                    node.dependencies.iter().map(move |(dep, requirement)| {
+                        (node.name.as_str(), dep.as_str(), requirement)
+                    })
+                })
+                .collect::<Vec<_>>();
+            print_dot(deps, &graph, graph.nodes.keys().map(String::as_str), &edges);
+        }
+    }
+    Ok(())
+}
+
+pub fn print_why(deps: &Dependancies, lib: &str, format: GraphFormat) -> Result<()> {
+    let graph = DependencyGraph::build(deps);
+    graph.warn_unreadable();
+    if !graph.nodes.contains_key(lib) {
+        return Err(anyhow!(
+            "{} is not a dependency of this project (is everything installed?)",
+            lib
+        ));
+    }
+    let paths = graph.paths_to(lib);
+
+    match format {
+        GraphFormat::Text => {
+            println!("{} is required by:", node_label(deps, &graph, lib).bold());
+            for path in paths.iter() {
+                if path.len() == 1 {
+                    println!("  {}", "hmm.json".green());
+                    continue;
+                }
+                let steps = path
+                    .windows(2)
+                    .map(|pair| {
+                        format!("{} ({})", pair[1], requirement(&graph, &pair[0], &pair[1]))
+                    })
+                    .collect::<Vec<_>>();
+                println!("  {} -> {}", path[0].green(), steps.join(" -> "));
+            }
+        }
+        GraphFormat::Json => {
+            let value = json!({
+                "name": lib,
+                "pinned": graph.nodes[lib].pinned,
+                "paths": paths,
+            });
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+        GraphFormat::Dot => {
+            let mut nodes: Vec<&str> = Vec::new();
+            let mut edges: Vec<(&str, &str, &Requirement)> = Vec::new();
+            for path in paths.iter() {
+                for name in path.iter() {
+                    if !nodes.contains(&name.as_str()) {
+                        nodes.push(name);
+                    }
+                }
+                for pair in path.windows(2) {
+                    let req = requirement(&graph, &pair[0], &pair[1]);
+                    if !edges
+                        .iter()
+                        .any(|(from, to, _)| *from == pair[0] && *to == pair[1])
+                    {
+                        edges.push((&pair[0], &pair[1], req));
+                    }
+                }
+            }
+            print_dot(deps, &graph, nodes.into_iter(), &edges);
+        }
+    }
+    Ok(())
+}
+
+/// `name version`, using the installed haxelib.json version, or what hmm.json pins
+fn node_label(deps: &Dependancies, graph: &DependencyGraph, name: &str) -> String {
+    match node_version(deps, graph.nodes.get(name)) {
+        Some(version) => format!("{} {}", name, version),
+        None => name.to_string(),
+    }
+}
+
+fn node_version(deps: &Dependancies, node: Option<&GraphNode>) -> Option<String> {
+    let node = node?;
+    if let Some(version) = &node.installed_version {
+        return Some(version.clone());
+    }
+    let lib = deps.get_haxelib(&node.name).ok()?;
+    lib.version.clone().or_else(|| lib.vcs_ref.clone())
+}
+
+fn requirement<'a>(graph: &'a DependencyGraph, from: &str, to: &str) -> &'a Requirement {
+    graph.nodes[from]
+        .dependencies
+        .iter()
+        .find(|(dep, _)| dep == to)
+        .map(|(_, requirement)| requirement)
+        .expect("paths only follow existing edges")
+}
+
+fn print_children<'a>(
+    deps: &Dependancies,
+    graph: &'a DependencyGraph,
+    name: &str,
+    prefix: &str,
+    ancestors: &mut Vec<&'a str>,
+    expanded: &mut HashSet<&'a str>,
+) {
+    let Some(node) = graph.nodes.get(name) else {
+        return;
+    };
+    for (i, (dep, requirement)) in node.dependencies.iter().enumerate() {
+        let last = i == node.dependencies.len() - 1;
+        let branch = if last { "└── " } else { "├── " };
+        let mut line = format!(
+            "{}{}{} {}",
+            prefix,
+            branch,
+            node_label(deps, graph, dep),
+            format!("(wants {})", requirement).dimmed()
+        );
+
+        let child = graph
+            .nodes
+            .get_key_value(dep.as_str())
+            .map(|(k, _)| k.as_str());
+        let Some(child) = child else {
+            println!("{}", line);
+            continue;
+        };
+        if ancestors.contains(&child) {
+            line.push_str(&format!(" {}", "(cycle)".red()));
+            println!("{}", line);
+            continue;
+        }
+        // Like `cargo tree`, only expand each library once and mark repeats with (*)
+        if !expanded.insert(child) && !graph.nodes[child].dependencies.is_empty() {
+            line.push_str(" (*)");
+            println!("{}", line);
+            continue;
+        }
+        if graph.nodes[child].path.is_none() {
+            line.push_str(&format!(" {}", "(not installed)".yellow()));
+        }
+        println!("{}", line);
+
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        ancestors.push(child);
+        print_children(deps, graph, child, &child_prefix, ancestors, expanded);
+        ancestors.pop();
+    }
+}
+
+/// `name`, `version`, `pinned`, `installed` and `wants` of a `tree --output json` entry
+fn node_json(
+    deps: &Dependancies,
+    graph: &DependencyGraph,
+    name: &str,
+    wants: Option<&Requirement>,
+) -> Value {
+    let node = graph.nodes.get(name);
+    let mut value = json!({
+        "name": name,
+        "version": node_version(deps, node),
+        "pinned": node.is_some_and(|n| n.pinned),
+        "installed": node.is_some_and(|n| n.path.is_some()),
+    });
+    if let Some(wants) = wants {
+        value["wants"] = json!(wants.spec());
+    }
+    value
+}
+
+/// Like the text output, a library's `dependencies` are only listed the first time it shows
+/// up. Later entries for it are marked `"repeated": true` instead
+fn tree_json<'a>(
+    deps: &Dependancies,
+    graph: &'a DependencyGraph,
+    name: &'a str,
+    wants: Option<&Requirement>,
+    ancestors: &mut Vec<&'a str>,
+    expanded: &mut HashSet<&'a str>,
+) -> Value {
+    let mut value = node_json(deps, graph, name, wants);
+    let node = graph.nodes.get(name);
+
+    let mut children = Vec::new();
+    for (dep, requirement) in node.map(|n| n.dependencies.as_slice()).unwrap_or_default() {
+        if ancestors.contains(&dep.as_str()) {
+            children.push(json!({ "name": dep, "wants": requirement.spec(), "cycle": true }));
+            continue;
+        }
+        let has_dependencies = graph
+            .nodes
+            .get(dep.as_str())
+            .is_some_and(|n| !n.dependencies.is_empty());
+        if !expanded.insert(dep) && has_dependencies {
+            let mut child = node_json(deps, graph, dep, Some(requirement));
+            child["repeated"] = json!(true);
+            children.push(child);
+            continue;
+        }
+        ancestors.push(dep);
+        children.push(tree_json(
+            deps,
+            graph,
+            dep,
+            Some(requirement),
+            ancestors,
+            expanded,
+        ));
+        ancestors.pop();
+    }
+    value["dependencies"] = Value::Array(children);
+    value
+}
+
+/// `value` escaped to go between the double quotes of a DOT string
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn print_dot<'a>(
+    deps: &Dependancies,
+    graph: &DependencyGraph,
+    nodes: impl Iterator<Item = &'a str>,
+    edges: &[(&str, &str, &Requirement)],
+) {
+    println!("digraph dependencies {{");
+    println!("    \"hmm.json\" [shape=box];");
+    for name in nodes {
+        let style = match graph.nodes.get(name) {
+            Some(node) if node.path.is_none() => ", style=dashed",
+            _ => "",
+        };
+        println!(
+            "    \"{}\" [label=\"{}\"{}];",
+            dot_escape(name),
+            dot_escape(&node_label(deps, graph, name)),
+            style
+        );
+        if graph.nodes.get(name).is_some_and(|n| n.pinned) {
+            println!("    \"hmm.json\" -> \"{}\";", dot_escape(name));
+        }
+    }
+    for (from, to, requirement) in edges {
+        println!(
+            "    \"{}\" -> \"{}\" [label=\"{}\"];",
+            dot_escape(from),
+            dot_escape(to),
+            dot_escape(&requirement.spec())
+        );
+    }
+    println!("}}");
+}
@@ -0,0 +1,437 @@
+use std::io::{stdin, stdout, Write};
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+
+use crate::commands::install_command::{self, InstallOptions};
+use crate::commands::outdated_command::{installed_git_commit, is_newer, ls_remote, parse_version};
+use crate::hmm::constraint;
+use crate::hmm::dependencies::Dependancies;
+use crate::hmm::haxelib::{git_repo_path_for_name, Haxelib, HaxelibType};
+use crate::hmm::haxelib_api::HaxelibApi;
+use crate::hmm::json;
+
+/// How far `update --semver` may move a pin
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SemverBump {
+    /// Stay on the same major version
+    Minor,
+    /// Stay on the same major and minor version
+    Patch,
+}
+
+pub struct UpdateOptions {
+    /// Only allow compatible releases, instead of the latest one
+    pub semver: Option<SemverBump>,
+    /// Print what would change without touching hmm.json or `.haxelib/`
+    pub dry_run: bool,
+    /// Ask before applying each update
+    pub interactive: bool,
+}
+
+/// A dependency name paired with the error that stopped its update from being worked out
+type LookupFailure = (String, anyhow::Error);
+
+/// A pin that `update` will move
+#[derive(Debug, PartialEq)]
+pub struct PendingUpdate {
+    pub name: String,
+    pub from: Option<String>,
+    pub to: String,
+    /// The branch a git dependency follows. Its `ref` stays the branch, and `to` is the commit
+    /// at the branch's tip that the checkout moves to
+    pub branch: Option<String>,
+}
+
+/// Moves the hmm.json pins of `names` (or every dependency) forward, then installs them.
+///
+/// Haxelibs go to their latest release, or the latest compatible one with `--semver`, staying
+/// within their hmm.json `constraint` if they have one.
+/// Git tags go to the newest tag the same way, and commits go to the tip of the default branch,
+/// written as a commit id. Branches are left as they are in hmm.json, and their checkout is
+/// moved to the tip of the branch.
+pub fn update(
+    api: &HaxelibApi,
+    deps: Dependancies,
+    names: &[String],
+    json_path: PathBuf,
+    options: &UpdateOptions,
+    install_options: &InstallOptions,
+) -> Result<()> {
+    let proxy = install_options.proxy.as_deref();
+    let (updates, failures) = plan(api, &deps, names, options.semver, proxy);
+    if updates.is_empty() && failures.is_empty() {
+        println!("All dependencies are up to date");
+        return Ok(());
+    }
+
+    let result = match updates.is_empty() {
+        true => Ok(()),
+        false => apply(deps, updates, json_path, options, install_options),
+    };
+    // the updates that could be worked out are applied, but the command still fails
+    let lookups = report_lookup_failures(&failures);
+    result.and(lookups)
+}
+
+/// Writes `updates` to hmm.json and installs them, after asking about each with `--interactive`
+fn apply(
+    mut deps: Dependancies,
+    mut updates: Vec<PendingUpdate>,
+    json_path: PathBuf,
+    options: &UpdateOptions,
+    install_options: &InstallOptions,
+) -> Result<()> {
+    if options.interactive {
+        updates = pick(updates)?;
+        if updates.is_empty() {
+            println!("Nothing selected, hmm.json is unchanged");
+            return Ok(());
+        }
+    }
+
+    for update in updates.iter() {
+        println!(
+            "{} {} {} -> {}",
+            match options.dry_run {
+                true => "Would update",
+                false => "Updating",
+            },
+            update.name.green().bold(),
+            update.from.as_deref().unwrap_or("(unpinned)").red(),
+            update.to.green()
+        );
+    }
+    if options.dry_run {
+        return Ok(());
+    }
+
+    for update in updates.iter() {
+        let lib = deps
+            .dependencies
+            .iter_mut()
+            .find(|lib| lib.name == update.name)
+            .ok_or_else(|| anyhow!("{} is not in hmm.json", update.name))?;
+        match (&lib.haxelib_type, &update.branch) {
+            (HaxelibType::Haxelib, _) => lib.version = Some(update.to.clone()),
+            // still following its branch
+            (_, Some(_)) => {}
+            _ => lib.vcs_ref = Some(update.to.clone()),
+        }
+        // the old hash belongs to the old version
+        lib.integrity = None;
+    }
+    json::save_json(deps.clone(), json_path)?;
+
+    // branch checkouts are installed like a pin on the tip, then put back on their branch
+    let mut install_deps = deps.clone();
+    for update in updates.iter().filter(|update| update.branch.is_some()) {
+        if let Some(lib) = install_deps
+            .dependencies
+            .iter_mut()
+            .find(|lib| lib.name == update.name)
+        {
+            lib.vcs_ref = Some(update.to.clone());
+        }
+    }
+    let updated: Vec<String> = updates.iter().map(|update| update.name.clone()).collect();
+    install_command::install_from_hmm(&install_deps, &updated, install_options)?;
+
+    for update in updates.iter() {
+        if let Some(branch) = &update.branch {
+            track_branch(&update.name, branch)?;
+        }
+    }
+    Ok(())
+}
+
+/// Works out the new pin of every dependency that has one, and which dependencies couldn't be
+/// looked up
+fn plan(
+    api: &HaxelibApi,
+    deps: &Dependancies,
+    names: &[String],
+    semver: Option<SemverBump>,
+    proxy: Option<&str>,
+) -> (Vec<PendingUpdate>, Vec<LookupFailure>) {
+    let mut updates = Vec::new();
+    let mut failures = Vec::new();
+    for lib in deps.filter_by_names(names) {
+        let update = match lib.haxelib_type {
+            HaxelibType::Haxelib => haxelib_update(api, lib, semver).map(|to| {
+                to.map(|to| PendingUpdate {
+                    name: lib.name.clone(),
+                    from: lib.version.clone(),
+                    to,
+                    branch: None,
+                })
+            }),
+            HaxelibType::Git => git_update(lib, semver, proxy),
+            HaxelibType::Dev | HaxelibType::Mecurial => continue,
+        };
+        match update {
+            Ok(Some(update)) => updates.push(update),
+            Ok(None) => {}
+            Err(e) => failures.push((lib.name.clone(), e)),
+        }
+    }
+    (updates, failures)
+}
+
+fn report_lookup_failures(failures: &[LookupFailure]) -> Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    eprintln!(
+        "{} {}",
+        failures.len().to_string().red().bold(),
+        "dependencies couldn't be checked for updates:".red()
+    );
+    for (name, e) in failures {
+        eprintln!("  {}: {:#}", name.red().bold(), e);
+    }
+    Err(anyhow!(
+        "Failed to look up updates for {} dependencies",
+        failures.len()
+    ))
+}
+
+fn haxelib_update(
+    api: &HaxelibApi,
+    lib: &Haxelib,
+    semver: Option<SemverBump>,
+) -> Result<Option<String>> {
+    let infos = api.infos(&lib.name)?;
+    let mut versions: Vec<&str> = infos.versions.iter().map(|v| v.name.as_str()).collect();
+
+    // a constraint narrows what the pin may move to
+    if let Some(range) = lib.constraint.as_deref() {
+        let req = constraint::parse(range)?;
+        versions.retain(|v| constraint::matches(&req, v));
+        let Some(current) = lib.version.as_deref() else {
+            return Ok(constraint::resolve(&req, &versions).map(str::to_string));
+        };
+        return Ok(match semver {
+            Some(_) => pick_version(current, &versions, semver),
+            None => constraint::resolve(&req, &versions).filter(|v| is_newer(v, current)),
+        }
+        .map(str::to_string));
+    }
+
+    let Some(current) = lib.version.as_deref() else {
+        return Ok(Some(infos.curversion));
+    };
+    Ok(match semver {
+        Some(_) => pick_version(current, &versions, semver).map(str::to_string),
+        None => is_newer(&infos.curversion, current).then_some(infos.curversion.clone()),
+    })
+}
+
+fn git_update(
+    lib: &Haxelib,
+    semver: Option<SemverBump>,
+    proxy: Option<&str>,
+) -> Result<Option<PendingUpdate>> {
+    let url = lib.url()?;
+    let refs = ls_remote(url, proxy)?;
+    let current = lib.vcs_ref.as_deref();
+    let pending = |to: &str| PendingUpdate {
+        name: lib.name.clone(),
+        from: lib.vcs_ref.clone(),
+        to: to.to_string(),
+        branch: None,
+    };
+
+    if let Some(tag) = current.filter(|r| refs.tags.contains_key(*r)) {
+        let tags: Vec<&str> = refs.tags.keys().map(String::as_str).collect();
+        return Ok(pick_version(tag, &tags, semver).map(pending));
+    }
+
+    if let Some((branch, tip)) = current.and_then(|r| refs.branches.get_key_value(r)) {
+        let checkout = installed_git_commit(&lib.name);
+        if checkout.as_ref() == Some(tip) {
+            return Ok(None);
+        }
+        return Ok(Some(PendingUpdate {
+            name: lib.name.clone(),
+            from: Some(match checkout {
+                Some(commit) => format!("{}@{}", branch, commit),
+                None => branch.clone(),
+            }),
+            to: tip.clone(),
+            branch: Some(branch.clone()),
+        }));
+    }
+
+    let tip = refs
+        .head
+        .as_ref()
+        .ok_or_else(|| anyhow!("{} has no HEAD", url))?;
+    Ok(match current {
+        Some(current) if tip.starts_with(current) => None,
+        _ => Some(pending(tip)),
+    })
+}
+
+/// Puts the checkout of `name`, just installed at the tip of `branch`, back on that branch
+fn track_branch(name: &str, branch: &str) -> Result<()> {
+    let repo = git_repo_path_for_name(name).to_string_lossy().to_string();
+    let status = Command::new("git")
+        .args(["-C", &repo, "checkout", "-q", "-B", branch])
+        .status()
+        .context("Failed to execute git checkout")?;
+    if !status.success() {
+        return Err(anyhow!("{}: couldn't check out branch {}", name, branch));
+    }
+    Ok(())
+}
+
+/// The highest of `candidates` that is newer than `current` and within `semver`'s range.
+/// Prereleases are only picked when `current` is one
+pub fn pick_version<'a>(
+    current: &str,
+    candidates: &[&'a str],
+    semver: Option<SemverBump>,
+) -> Option<&'a str> {
+    let current = parse_version(current)?;
+    candidates
+        .iter()
+        .filter_map(|candidate| parse_version(candidate).map(|version| (version, *candidate)))
+        .filter(|(version, _)| *version > current)
+        .filter(|(version, _)| version.pre.is_empty() || !current.pre.is_empty())
+        .filter(|(version, _)| match semver {
+            None => true,
+            Some(SemverBump::Minor) => version.major == current.major,
+            Some(SemverBump::Patch) => {
+                version.major == current.major && version.minor == current.minor
+            }
+        })
+        .max()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Asks about each update in turn, keeping the ones answered with yes
+fn pick(updates: Vec<PendingUpdate>) -> Result<Vec<PendingUpdate>> {
+    let mut picked = Vec::new();
+    for update in updates {
+        print!(
+            "Update {} {} -> {}? (y/N): ",
+            update.name.bold(),
+            update.from.as_deref().unwrap_or("(unpinned)").red(),
+            update.to.green()
+        );
+        stdout().flush()?;
+
+        let mut input = String::new();
+        stdin().read_line(&mut input)?;
+        if matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            picked.push(update);
+        }
+    }
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hmm::haxe_serializer::Value;
+    use crate::hmm::haxelib_api::mock::{self, object, remoting_response, string};
+
+    const VERSIONS: [&str; 6] = ["1.0.0", "1.0.3", "1.2.0", "1.3.0-rc.1", "2.0.0", "nightly"];
+
+    #[test]
+    fn test_pick_version() {
+        assert_eq!(pick_version("1.0.0", &VERSIONS, None), Some("2.0.0"));
+        assert_eq!(
+            pick_version("1.0.0", &VERSIONS, Some(SemverBump::Minor)),
+            Some("1.2.0")
+        );
+        assert_eq!(
+            pick_version("1.0.0", &VERSIONS, Some(SemverBump::Patch)),
+            Some("1.0.3")
+        );
+        assert_eq!(pick_version("2.0.0", &VERSIONS, None), None);
+        assert_eq!(pick_version("nightly", &VERSIONS, None), None);
+    }
+
+    #[test]
+    fn test_pick_version_prereleases() {
+        assert_eq!(
+            pick_version("1.3.0-beta.1", &VERSIONS, Some(SemverBump::Patch)),
+            Some("1.3.0-rc.1")
+        );
+        assert_eq!(
+            pick_version("v1.0.0", &["v1.1.0", "v1.0.1"], Some(SemverBump::Patch)),
+            Some("v1.0.1")
+        );
+    }
+
+    #[test]
+    fn test_plan_haxelib_updates() {
+        let (url, _requests) = mock::serve(|_| {
+            let versions = ["4.0.0", "4.1.0", "5.0.0"]
+                .iter()
+                .map(|v| object(vec![("name", string(v))]))
+                .collect();
+            remoting_response(object(vec![
+                ("name", string("lime")),
+                ("curversion", string("5.0.0")),
+                ("versions", Value::Array(versions)),
+            ]))
+        });
+        let api = HaxelibApi::new(&url);
+        let deps: Dependancies = serde_json::from_str(
+            r#"{"dependencies": [
+                {"name": "lime", "type": "haxelib", "version": "4.0.0"},
+                {"name": "local", "type": "dev", "path": "../local"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let lime = |to: &str| PendingUpdate {
+            name: "lime".to_string(),
+            from: Some("4.0.0".to_string()),
+            to: to.to_string(),
+            branch: None,
+        };
+        assert_eq!(plan(&api, &deps, &[], None, None).0, vec![lime("5.0.0")]);
+        assert_eq!(
+            plan(&api, &deps, &[], Some(SemverBump::Minor), None).0,
+            vec![lime("4.1.0")]
+        );
+        assert_eq!(
+            plan(&api, &deps, &[], Some(SemverBump::Patch), None).0,
+            vec![]
+        );
+
+        let constrained: Dependancies = serde_json::from_str(
+            r#"{"dependencies": [
+                {"name": "lime", "type": "haxelib", "version": "4.0.0", "constraint": "^4"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            plan(&api, &constrained, &[], None, None).0,
+            vec![lime("4.1.0")]
+        );
+    }
+
+    #[test]
+    fn test_plan_collects_failed_lookups() {
+        let url = mock::failing("503 Service Unavailable");
+        let api = HaxelibApi::new(&url);
+        let deps: Dependancies = serde_json::from_str(
+            r#"{"dependencies": [{"name": "lime", "type": "haxelib", "version": "4.0.0"}]}"#,
+        )
+        .unwrap();
+
+        let (updates, failures) = plan(&api, &deps, &[], None, None);
+        assert_eq!(updates, vec![]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "lime");
+    }
+}
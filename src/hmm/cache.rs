@@ -0,0 +1,510 @@
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+
+/// Name of the cache folder inside the platform cache directory
+const CACHE_FOLDER: &str = "hmm-rs";
+
+/// User-level cache shared by every project on the machine.
+///
+/// Layout:
+/// - `haxelib/<name,with,commas>/<version>/<sha256>.zip` downloaded haxelib zips
+/// - `git/<repo>-<url hash>.git` bare mirrors of git dependencies, keyed by URL
+/// - `tmp/` in-progress downloads, moved into place once complete
+pub struct Cache {
+    root: PathBuf,
+}
+
+/// A single item stored in the cache, as listed by `hmm-rs cache ls`
+pub struct CacheEntry {
+    pub kind: CacheEntryKind,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+pub enum CacheEntryKind {
+    HaxelibZip {
+        name: String,
+        version: String,
+        sha256: String,
+    },
+    GitMirror {
+        url: Option<String>,
+    },
+}
+
+/// Resolve the cache root: $HMM_CACHE_DIR > $XDG_CACHE_HOME/hmm-rs > platform default.
+/// Empty strings are treated as "not set" and fall through.
+pub fn cache_dir() -> PathBuf {
+    let from_env = |key: &str| {
+        env::var_os(key)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+
+    if let Some(dir) = from_env("HMM_CACHE_DIR") {
+        return dir;
+    }
+    if let Some(dir) = from_env("XDG_CACHE_HOME") {
+        return dir.join(CACHE_FOLDER);
+    }
+    if cfg!(windows) {
+        if let Some(dir) = from_env("LOCALAPPDATA") {
+            return dir.join(CACHE_FOLDER);
+        }
+    } else if let Some(home) = from_env("HOME") {
+        if cfg!(target_os = "macos") {
+            return home.join("Library").join("Caches").join(CACHE_FOLDER);
+        }
+        return home.join(".cache").join(CACHE_FOLDER);
+    }
+    env::temp_dir().join(CACHE_FOLDER)
+}
+
+impl Cache {
+    pub fn open() -> Self {
+        Self::at(cache_dir())
+    }
+
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn haxelib_root(&self) -> PathBuf {
+        self.root.join("haxelib")
+    }
+
+    fn git_root(&self) -> PathBuf {
+        self.root.join("git")
+    }
+
+    fn tmp_root(&self) -> PathBuf {
+        self.root.join("tmp")
+    }
+
+    /// Directory holding every cached zip of `name` at `version`
+    pub fn haxelib_version_dir(&self, name: &str, version: &str) -> PathBuf {
+        self.haxelib_root()
+            .join(name.replace('.', ","))
+            .join(version)
+    }
+
+    /// Returns the zip for `name` at `version` whose sha256 is `integrity`, or without an
+    /// `integrity` the most recently stored one, if any
+    pub fn find_haxelib_zip(
+        &self,
+        name: &str,
+        version: &str,
+        integrity: Option<&str>,
+    ) -> Option<PathBuf> {
+        let dir = self.haxelib_version_dir(name, version);
+        if let Some(integrity) = integrity {
+            let zip = dir.join(format!("{}.zip", integrity.to_ascii_lowercase()));
+            return zip.is_file().then_some(zip);
+        }
+        let entries = fs::read_dir(dir).ok()?;
+
+        entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "zip"))
+            .max_by_key(|p| modified_time(p))
+    }
+
+    /// Path a download should be written to before it is stored with `store_haxelib_zip`.
+    /// Every call gets a new one, so concurrent installs never write to the same file
+    pub fn download_path(&self, name: &str, version: &str) -> Result<PathBuf> {
+        let tmp = self.tmp_root();
+        fs::create_dir_all(&tmp)
+            .with_context(|| format!("Failed to create cache directory {}", tmp.display()))?;
+        Ok(tmp.join(format!(
+            "{}-{}.{}.zip.part",
+            name.replace('.', ","),
+            version,
+            unique_suffix()
+        )))
+    }
+
+    /// Moves a finished download into the cache, keyed by its sha256, and returns the new path
+    pub fn store_haxelib_zip(&self, name: &str, version: &str, download: &Path) -> Result<PathBuf> {
+        let hash = sha256_file(download)?;
+        let dir = self.haxelib_version_dir(name, version);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+
+        let target = dir.join(format!("{}.zip", hash));
+        if target.exists() {
+            // another install stored the very same zip in the meantime
+            fs::remove_file(download)?;
+            return Ok(target);
+        }
+        fs::rename(download, &target).with_context(|| {
+            format!(
+                "Failed to move {} into the cache at {}",
+                download.display(),
+                target.display()
+            )
+        })?;
+        Ok(target)
+    }
+
+    /// Bare mirror location for the git repository at `url`
+    pub fn git_mirror_path(&self, url: &str) -> PathBuf {
+        let url = url.trim().trim_end_matches('/');
+        let repo = url
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(".git");
+        let repo: String = repo
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '_',
+            })
+            .collect();
+
+        let url_hash = hex_digest(Sha256::digest(url.as_bytes()).as_slice());
+        self.git_root()
+            .join(format!("{}-{}.git", repo, &url_hash[..16]))
+    }
+
+    /// Marks a cache entry as recently used, so `prune` keeps it around
+    pub fn touch(&self, path: &Path) {
+        if let Ok(file) = File::options().append(true).open(path) {
+            let _ = file.set_modified(SystemTime::now());
+        } else {
+            // directories (git mirrors) can't be opened for writing on every platform
+            let _ = touch_dir(path);
+        }
+    }
+
+    /// Every haxelib zip and git mirror currently in the cache
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+
+        for lib_dir in read_dir_paths(&self.haxelib_root())? {
+            let name = file_name(&lib_dir).replace(',', ".");
+            for version_dir in read_dir_paths(&lib_dir)? {
+                let version = file_name(&version_dir);
+                for zip in read_dir_paths(&version_dir)? {
+                    if zip.extension().is_none_or(|ext| ext != "zip") {
+                        continue;
+                    }
+                    let metadata = fs::metadata(&zip)?;
+                    entries.push(CacheEntry {
+                        kind: CacheEntryKind::HaxelibZip {
+                            name: name.clone(),
+                            version: version.clone(),
+                            sha256: zip
+                                .file_stem()
+                                .map(|s| s.to_string_lossy().to_string())
+                                .unwrap_or_default(),
+                        },
+                        size: metadata.len(),
+                        modified: metadata.modified()?,
+                        path: zip,
+                    });
+                }
+            }
+        }
+
+        for mirror in read_dir_paths(&self.git_root())? {
+            if !mirror.is_dir() {
+                continue;
+            }
+            entries.push(CacheEntry {
+                kind: CacheEntryKind::GitMirror {
+                    url: mirror_origin_url(&mirror),
+                },
+                size: dir_size(&mirror)?,
+                modified: modified_time(&mirror),
+                path: mirror,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Removes the whole cache
+    pub fn clean(&self) -> Result<()> {
+        if self.root.exists() {
+            fs::remove_dir_all(&self.root)
+                .with_context(|| format!("Failed to remove {}", self.root.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Removes entries (and leftover partial downloads) that haven't been used
+    /// for `max_age`. Returns the removed entries.
+    pub fn prune(&self, max_age: Duration) -> Result<Vec<CacheEntry>> {
+        let now = SystemTime::now();
+        let mut removed = Vec::new();
+
+        for entry in self.entries()? {
+            let age = now.duration_since(entry.modified).unwrap_or_default();
+            if age < max_age {
+                continue;
+            }
+            match entry.kind {
+                CacheEntryKind::HaxelibZip { .. } => fs::remove_file(&entry.path)?,
+                CacheEntryKind::GitMirror { .. } => fs::remove_dir_all(&entry.path)?,
+            }
+            removed.push(entry);
+        }
+
+        // partial downloads left behind by interrupted installs
+        for part in read_dir_paths(&self.tmp_root())? {
+            let age = now.duration_since(modified_time(&part)).unwrap_or_default();
+            if age >= max_age {
+                let _ = fs::remove_file(&part);
+            }
+        }
+
+        // drop the now empty haxelib/<name>/<version> folders
+        for lib_dir in read_dir_paths(&self.haxelib_root())? {
+            for version_dir in read_dir_paths(&lib_dir)? {
+                let _ = fs::remove_dir(&version_dir);
+            }
+            let _ = fs::remove_dir(&lib_dir);
+        }
+
+        Ok(removed)
+    }
+}
+
+/// A suffix for temporary files and folders that no other install, in this process or any
+/// other hmm-rs running at the same time, will use
+pub fn unique_suffix() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Lowercase hex sha256 of a file's contents
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex_digest(hasher.finalize().as_slice()))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn read_dir_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    match fs::read_dir(dir) {
+        Ok(entries) => {
+            let mut paths = entries
+                .map(|e| e.map(|e| e.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            paths.sort();
+            Ok(paths)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(anyhow!("Failed to read {}: {}", dir.display(), e)),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn modified_time(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut total = 0;
+    for path in read_dir_paths(dir)? {
+        let metadata = fs::symlink_metadata(&path)?;
+        total += if metadata.is_dir() {
+            dir_size(&path)?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(total)
+}
+
+/// Bumps a directory's mtime by creating and removing a marker file inside it
+fn touch_dir(dir: &Path) -> io::Result<()> {
+    let marker = dir.join("hmm-rs-last-used");
+    File::create(&marker)?;
+    fs::remove_file(&marker)
+}
+
+fn mirror_origin_url(mirror: &Path) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .arg("--git-dir")
+        .arg(mirror)
+        .args(["config", "remote.origin.url"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haxelib_version_dir_uses_commas() {
+        let cache = Cache::at("/cache");
+        assert_eq!(
+            cache.haxelib_version_dir("funkin.vis", "1.0.0"),
+            PathBuf::from("/cache/haxelib/funkin,vis/1.0.0")
+        );
+    }
+
+    #[test]
+    fn test_git_mirror_path_is_stable_per_url() {
+        let cache = Cache::at("/cache");
+        let a = cache.git_mirror_path("https://github.com/HaxeFlixel/flixel.git");
+        let b = cache.git_mirror_path("https://github.com/HaxeFlixel/flixel.git");
+        let other = cache.git_mirror_path("https://github.com/someone-else/flixel.git");
+        assert_eq!(a, b);
+        assert_ne!(a, other);
+        assert!(file_name(&a).starts_with("flixel-"));
+        assert!(file_name(&a).ends_with(".git"));
+    }
+
+    #[test]
+    fn test_git_mirror_path_ssh_url() {
+        let cache = Cache::at("/cache");
+        let path = cache.git_mirror_path("git@github.com:user/repo.git");
+        assert!(file_name(&path).starts_with("repo-"));
+    }
+
+    #[test]
+    fn test_store_and_find_haxelib_zip() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let cache = Cache::at(temp.path());
+
+        assert!(cache.find_haxelib_zip("lime", "8.0.0", None).is_none());
+
+        let download = cache.download_path("lime", "8.0.0").unwrap();
+        fs::write(&download, b"zip bytes").unwrap();
+        let stored = cache.store_haxelib_zip("lime", "8.0.0", &download).unwrap();
+
+        assert!(!download.exists());
+        assert_eq!(
+            cache.find_haxelib_zip("lime", "8.0.0", None),
+            Some(stored.clone())
+        );
+        assert_eq!(
+            file_name(&stored),
+            format!("{}.zip", sha256_file(&stored).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_find_haxelib_zip_by_integrity() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let cache = Cache::at(temp.path());
+        let mut stored = Vec::new();
+        for bytes in ["first upload", "republished"] {
+            let download = cache.download_path("lime", "8.0.0").unwrap();
+            fs::write(&download, bytes).unwrap();
+            stored.push(cache.store_haxelib_zip("lime", "8.0.0", &download).unwrap());
+        }
+        let first = sha256_file(&stored[0]).unwrap();
+
+        assert_eq!(
+            cache.find_haxelib_zip("lime", "8.0.0", Some(&first.to_uppercase())),
+            Some(stored[0].clone())
+        );
+        assert_eq!(
+            cache.find_haxelib_zip("lime", "8.0.0", Some(&"0".repeat(64))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_download_paths_are_unique() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let cache = Cache::at(temp.path());
+
+        let first = cache.download_path("lime", "8.0.0").unwrap();
+        let second = cache.download_path("lime", "8.0.0").unwrap();
+        assert_ne!(first, second);
+        assert!(file_name(&first).starts_with("lime-8.0.0."));
+
+        // both finish with the same bytes, the second just joins the first in the cache
+        fs::write(&first, b"zip bytes").unwrap();
+        fs::write(&second, b"zip bytes").unwrap();
+        let stored = cache.store_haxelib_zip("lime", "8.0.0", &first).unwrap();
+        assert_eq!(
+            cache.store_haxelib_zip("lime", "8.0.0", &second).unwrap(),
+            stored
+        );
+        assert!(!second.exists());
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("hello.txt");
+        fs::write(&path, b"hello").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_prune_removes_old_entries_only() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let cache = Cache::at(temp.path());
+
+        for version in ["1.0.0", "2.0.0"] {
+            let download = cache.download_path("lib", version).unwrap();
+            fs::write(&download, version).unwrap();
+            cache.store_haxelib_zip("lib", version, &download).unwrap();
+        }
+        let old = cache.find_haxelib_zip("lib", "1.0.0", None).unwrap();
+        File::options()
+            .append(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60 * 60 * 24 * 60))
+            .unwrap();
+
+        let removed = cache.prune(Duration::from_secs(60 * 60 * 24 * 30)).unwrap();
+
+        assert_eq!(removed.len(), 1);
+        assert!(!old.exists());
+        assert!(!cache.haxelib_version_dir("lib", "1.0.0").exists());
+        assert!(cache.find_haxelib_zip("lib", "2.0.0", None).is_some());
+    }
+
+    #[test]
+    fn test_entries_on_missing_cache_is_empty() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let cache = Cache::at(temp.path().join("nope"));
+        assert!(cache.entries().unwrap().is_empty());
+    }
+}
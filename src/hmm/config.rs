@@ -0,0 +1,396 @@
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::install_command::GitClone;
+use crate::Color;
+
+/// Name of the config folder inside the platform config directory
+const CONFIG_FOLDER: &str = "hmm-rs";
+
+/// Name of the project config file, read from the folder holding hmm.json
+pub const PROJECT_CONFIG_FILE: &str = "hmm.toml";
+
+/// Every key `hmm-rs config` accepts, in the order `config list` prints them
+pub const KEYS: [&str; 10] = [
+    "haxelib-server",
+    "haxelib-mirrors",
+    "cache-dir",
+    "jobs",
+    "retries",
+    "retry-delay-ms",
+    "remote-separator",
+    "git-clone",
+    "color",
+    "proxy",
+];
+
+/// Settings that can come from a config file, the environment or a global flag.
+/// Anything left unset falls through to the next layer down, then to the built-in default
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub haxelib_server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub haxelib_mirrors: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_delay_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_separator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_clone: Option<GitClone>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+}
+
+impl Config {
+    /// Reads a config file, which is fine to be missing
+    pub fn read(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&contents)
+            .map_err(anyhow::Error::from)
+            .and_then(Config::check)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Rejects settings that parse but can't be used, like `jobs = 0`
+    fn check(self) -> Result<Self> {
+        if self.jobs == Some(0) {
+            return Err(anyhow!("jobs must be at least 1"));
+        }
+        Ok(self)
+    }
+
+    /// The `HMM_*` environment variables. Empty ones are treated as unset
+    pub fn from_env() -> Result<Self> {
+        let var = |key: &str| env::var(key).ok().filter(|v| !v.is_empty());
+        // parsed straight into the field's type, so a value that doesn't fit is an error too
+        fn number<T: std::str::FromStr>(key: &str, value: Option<String>) -> Result<Option<T>> {
+            value
+                .map(|v| {
+                    v.parse::<T>()
+                        .map_err(|_| anyhow!("{} must be a number, got {}", key, v))
+                })
+                .transpose()
+        }
+        let git_clone = var("HMM_GIT_CLONE")
+            .map(|v| GitClone::from_str(&v, true).map_err(|e| anyhow!("HMM_GIT_CLONE: {}", e)))
+            .transpose()?;
+        let color = var("HMM_COLOR")
+            .map(|v| Color::from_str(&v, true).map_err(|e| anyhow!("HMM_COLOR: {}", e)))
+            .transpose()?;
+        let jobs = number("HMM_JOBS", var("HMM_JOBS"))?;
+        if jobs == Some(0) {
+            return Err(anyhow!("HMM_JOBS must be at least 1, got 0"));
+        }
+
+        Ok(Self {
+            haxelib_server: var("HMM_HAXELIB_SERVER"),
+            haxelib_mirrors: var("HMM_HAXELIB_MIRRORS")
+                .map(|mirrors| mirrors.split(',').map(|m| m.trim().to_string()).collect()),
+            cache_dir: var("HMM_CACHE_DIR").map(PathBuf::from),
+            jobs,
+            retries: number("HMM_RETRIES", var("HMM_RETRIES"))?,
+            retry_delay_ms: number("HMM_RETRY_DELAY_MS", var("HMM_RETRY_DELAY_MS"))?,
+            remote_separator: var("HMM_REMOTE_SEPARATOR"),
+            git_clone,
+            color,
+            proxy: var("HMM_PROXY"),
+        })
+    }
+
+    /// `self` with every setting `other` has replaced by `other`'s
+    pub fn merge(self, other: Config) -> Config {
+        Config {
+            haxelib_server: other.haxelib_server.or(self.haxelib_server),
+            haxelib_mirrors: other.haxelib_mirrors.or(self.haxelib_mirrors),
+            cache_dir: other.cache_dir.or(self.cache_dir),
+            jobs: other.jobs.or(self.jobs),
+            retries: other.retries.or(self.retries),
+            retry_delay_ms: other.retry_delay_ms.or(self.retry_delay_ms),
+            remote_separator: other.remote_separator.or(self.remote_separator),
+            git_clone: other.git_clone.or(self.git_clone),
+            color: other.color.or(self.color),
+            proxy: other.proxy.or(self.proxy),
+        }
+    }
+
+    /// The settings that are set, keyed like the config files
+    pub fn to_table(&self) -> toml::Table {
+        toml::Table::try_from(self).expect("config always serializes to a table")
+    }
+}
+
+/// Where a setting came from, lowest priority first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    User,
+    Project,
+    Env,
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::User => "user",
+            Source::Project => "project",
+            Source::Env => "env",
+            Source::Flag => "flag",
+        })
+    }
+}
+
+/// Every config layer, lowest priority first
+pub struct Layers {
+    layers: Vec<(Source, Config)>,
+    /// `~/.config/hmm-rs/config.toml`, when there's a home to put it in
+    pub user_path: Option<PathBuf>,
+    /// `hmm.toml` next to hmm.json
+    pub project_path: PathBuf,
+}
+
+impl Layers {
+    /// Reads the user and project config files and the environment.
+    /// The project file is looked for next to `json_path`
+    pub fn load(json_path: &Path) -> Result<Self> {
+        let user_path = user_config_path();
+        let project_path = project_config_path(json_path);
+
+        let mut layers = Vec::new();
+        if let Some(path) = &user_path {
+            layers.push((Source::User, Config::read(path)?));
+        }
+        layers.push((Source::Project, Config::read(&project_path)?));
+        layers.push((Source::Env, Config::from_env()?));
+
+        Ok(Self {
+            layers,
+            user_path,
+            project_path,
+        })
+    }
+
+    /// Adds a layer on top of the others
+    pub fn push(&mut self, source: Source, config: Config) {
+        self.layers.push((source, config));
+    }
+
+    /// Every layer merged, higher priority ones winning
+    pub fn merged(&self) -> Config {
+        self.layers
+            .iter()
+            .fold(Config::default(), |merged, (_, config)| {
+                merged.merge(config.clone())
+            })
+    }
+
+    /// The effective value of `key` and the layer it comes from, if any layer sets it
+    pub fn get(&self, key: &str) -> Option<(toml::Value, Source)> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|(source, config)| config.to_table().remove(key).map(|v| (v, *source)))
+    }
+}
+
+/// $XDG_CONFIG_HOME/hmm-rs/config.toml > ~/.config/hmm-rs/config.toml
+/// (%APPDATA%\hmm-rs\config.toml on Windows). Empty strings are treated as "not set".
+pub fn user_config_path() -> Option<PathBuf> {
+    let from_env = |key: &str| {
+        env::var_os(key)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let dir = match from_env("XDG_CONFIG_HOME") {
+        Some(dir) => dir,
+        None if cfg!(windows) => from_env("APPDATA")?,
+        None => from_env("HOME")?.join(".config"),
+    };
+    Some(dir.join(CONFIG_FOLDER).join("config.toml"))
+}
+
+pub fn project_config_path(json_path: &Path) -> PathBuf {
+    json_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(PROJECT_CONFIG_FILE)
+}
+
+/// Checks `key` is one `hmm-rs config` knows about
+pub fn check_key(key: &str) -> Result<()> {
+    match KEYS.contains(&key) {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "Unknown config key {} (expected one of {})",
+            key,
+            KEYS.join(", ")
+        )),
+    }
+}
+
+/// Turns what was typed after `config set <key>` into the value stored for `key`.
+/// Mirrors are comma separated, like $HMM_HAXELIB_MIRRORS
+pub fn parse_value(key: &str, value: &str) -> Result<toml::Value> {
+    check_key(key)?;
+    let parsed = match key {
+        "jobs" | "retries" | "retry-delay-ms" => toml::Value::Integer(
+            value
+                .parse::<u32>()
+                .map_err(|_| anyhow!("{} must be a number, got {}", key, value))?
+                .into(),
+        ),
+        "haxelib-mirrors" => toml::Value::Array(
+            value
+                .split(',')
+                .map(|mirror| toml::Value::String(mirror.trim().to_string()))
+                .filter(|mirror| mirror.as_str() != Some(""))
+                .collect(),
+        ),
+        _ => toml::Value::String(value.to_string()),
+    };
+
+    // let serde check enum values like `git-clone` and `color`
+    let mut table = toml::Table::new();
+    table.insert(key.to_string(), parsed.clone());
+    toml::Value::Table(table)
+        .try_into::<Config>()
+        .map_err(|e| anyhow!("Invalid value for {}: {}", key, e.message()))?
+        .check()
+        .map_err(|e| anyhow!("Invalid value for {}: {}", key, e))?;
+    Ok(parsed)
+}
+
+/// Sets `key` in the config file at `path`, creating it if needed.
+/// Other settings in the file are kept, comments aren't
+pub fn set(path: &Path, key: &str, value: &str) -> Result<()> {
+    let value = parse_value(key, value)?;
+    let mut table = match path.exists() {
+        true => Config::read(path)?.to_table(),
+        false => toml::Table::new(),
+    };
+    table.insert(key.to_string(), value);
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, toml::to_string(&table)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_config() {
+        let config: Config = toml::from_str(
+            r#"
+            haxelib-server = "https://haxelib.example.com"
+            haxelib-mirrors = ["https://a.example.com", "https://b.example.com"]
+            jobs = 8
+            git-clone = "blobless"
+            color = "never"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.haxelib_server.as_deref(),
+            Some("https://haxelib.example.com")
+        );
+        assert_eq!(config.haxelib_mirrors.unwrap().len(), 2);
+        assert_eq!(config.jobs, Some(8));
+        assert_eq!(config.git_clone, Some(GitClone::Blobless));
+        assert_eq!(config.color, Some(Color::Never));
+
+        assert!(toml::from_str::<Config>("job = 8").is_err());
+        assert!(toml::from_str::<Config>("jobs = 0")
+            .unwrap()
+            .check()
+            .is_err());
+        assert!(toml::from_str::<Config>("git-clone = \"shallow\"").is_err());
+    }
+
+    #[test]
+    fn test_layers_later_wins() {
+        let user = Config {
+            jobs: Some(2),
+            proxy: Some("http://proxy:3128".to_string()),
+            ..Config::default()
+        };
+        let project = Config {
+            jobs: Some(8),
+            ..Config::default()
+        };
+        let layers = Layers {
+            layers: vec![(Source::User, user), (Source::Project, project)],
+            user_path: None,
+            project_path: PathBuf::from(PROJECT_CONFIG_FILE),
+        };
+
+        let merged = layers.merged();
+        assert_eq!(merged.jobs, Some(8));
+        assert_eq!(merged.proxy.as_deref(), Some("http://proxy:3128"));
+        assert_eq!(
+            layers.get("jobs"),
+            Some((toml::Value::Integer(8), Source::Project))
+        );
+        assert_eq!(
+            layers.get("proxy").map(|(_, source)| source),
+            Some(Source::User)
+        );
+        assert_eq!(layers.get("color"), None);
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("jobs", "6").unwrap(), toml::Value::Integer(6));
+        assert_eq!(
+            parse_value("retries", "0").unwrap(),
+            toml::Value::Integer(0)
+        );
+        assert_eq!(
+            parse_value("haxelib-mirrors", "https://a, https://b").unwrap(),
+            toml::Value::Array(vec!["https://a".into(), "https://b".into()])
+        );
+        assert_eq!(
+            parse_value("git-clone", "full").unwrap(),
+            toml::Value::String("full".to_string())
+        );
+        assert!(parse_value("jobs", "many").is_err());
+        assert!(parse_value("jobs", "0").is_err());
+        assert!(parse_value("color", "purple").is_err());
+        assert!(parse_value("colour", "never").is_err());
+    }
+
+    #[test]
+    fn test_project_config_path() {
+        assert_eq!(
+            project_config_path(Path::new("hmm.json")),
+            PathBuf::from("hmm.toml")
+        );
+        assert_eq!(
+            project_config_path(Path::new("game/hmm.json")),
+            PathBuf::from("game/hmm.toml")
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Context, Result};
+use semver::{Version, VersionReq};
+
+/// Parses a hmm.json `constraint`. Comparators may be separated by spaces like npm
+/// (`>=8.0.0 <9`) or by commas like cargo (`>=8.0.0, <9`)
+pub fn parse(constraint: &str) -> Result<VersionReq> {
+    if constraint.contains("||") {
+        return Err(anyhow!(
+            "constraint {} uses ||, which isn't supported",
+            constraint
+        ));
+    }
+
+    // glue operators written apart from their version (`>= 8.0.0`) back on
+    let mut comparators: Vec<String> = Vec::new();
+    let mut operator = String::new();
+    for token in constraint
+        .split([',', ' '])
+        .filter(|token| !token.is_empty())
+    {
+        match token.chars().all(|c| "<>=^~".contains(c)) {
+            true => operator.push_str(token),
+            false => comparators.push(format!("{}{}", std::mem::take(&mut operator), token)),
+        }
+    }
+    if !operator.is_empty() {
+        return Err(anyhow!("constraint {} ends with {}", constraint, operator));
+    }
+
+    VersionReq::parse(&comparators.join(", "))
+        .with_context(|| format!("Invalid constraint {}", constraint))
+}
+
+/// Whether `version` is within `req`. Versions that aren't semver never are
+pub fn matches(req: &VersionReq, version: &str) -> bool {
+    Version::parse(version).is_ok_and(|version| req.matches(&version))
+}
+
+/// The highest of `versions` within `req`
+pub fn resolve<'a>(req: &VersionReq, versions: &[&'a str]) -> Option<&'a str> {
+    versions
+        .iter()
+        .filter_map(|v| Version::parse(v).ok().map(|version| (version, *v)))
+        .filter(|(version, _)| req.matches(version))
+        .max()
+        .map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSIONS: [&str; 6] = ["5.1.0", "5.2.0", "5.2.3", "6.0.0-rc.1", "6.0.0", "8.4.1"];
+
+    #[test]
+    fn test_parse_separators() {
+        for constraint in [">=8.0.0 <9", ">=8.0.0, <9", ">= 8.0.0 < 9"] {
+            let req = parse(constraint).unwrap();
+            assert!(req.matches(&Version::new(8, 4, 1)), "{}", constraint);
+            assert!(!req.matches(&Version::new(9, 0, 0)), "{}", constraint);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("^5 || ^6").is_err());
+        assert!(parse(">=").is_err());
+        assert!(parse("five").is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve(&parse("^5.2").unwrap(), &VERSIONS), Some("5.2.3"));
+        assert_eq!(resolve(&parse("~5.1").unwrap(), &VERSIONS), Some("5.1.0"));
+        assert_eq!(resolve(&parse(">=6").unwrap(), &VERSIONS), Some("8.4.1"));
+        assert_eq!(resolve(&parse("^7").unwrap(), &VERSIONS), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let req = parse("^5.2").unwrap();
+        assert!(matches(&req, "5.2.0"));
+        assert!(!matches(&req, "6.0.0"));
+        assert!(!matches(&req, "nightly"));
+    }
+}
@@ -1,4 +1,5 @@
 use super::haxelib::{Haxelib, HaxelibType};
+use crate::OutputFormat;
 use anyhow::Result;
 use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
@@ -16,8 +17,16 @@ impl fmt::Display for Dependancies {
 }
 
 impl Dependancies {
-    pub fn print_string_list(&self, names: &[String]) -> Result<()> {
-        for haxelib in self.filter_by_names(names) {
+    pub fn print_string_list(&self, names: &[String], format: OutputFormat) -> Result<()> {
+        let haxelibs = self.filter_by_names(names);
+        if format == OutputFormat::Json {
+            // same shape as hmm.json itself
+            let json = serde_json::json!({ "dependencies": haxelibs });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+            return Ok(());
+        }
+
+        for haxelib in haxelibs {
             Self::print_haxelib(haxelib);
         }
         Ok(())
@@ -48,6 +57,21 @@ impl Dependancies {
         Err(anyhow::anyhow!("Haxelib not found"))
     }
 
+    /// Replaces the dependency with the same name as `lib`, or appends it.
+    /// Returns true if an existing entry was replaced
+    pub fn upsert(&mut self, lib: Haxelib) -> bool {
+        match self.dependencies.iter_mut().find(|d| d.name == lib.name) {
+            Some(existing) => {
+                *existing = lib;
+                true
+            }
+            None => {
+                self.dependencies.push(lib);
+                false
+            }
+        }
+    }
+
     pub fn print_haxelib(lib: &Haxelib) {
         let version_or_ref = match &lib.version {
             Some(v) => format!("version: {}", v),
@@ -64,6 +88,10 @@ impl Dependancies {
             haxelib_type = lib.haxelib_type
         );
 
+        if let Some(constraint) = &lib.constraint {
+            haxelib_output.push_str(&format!("constraint: {}\n", constraint))
+        }
+
         match lib.haxelib_type {
             HaxelibType::Git => {
                 if let Some(u) = &lib.url {
@@ -97,6 +125,8 @@ mod tests {
                     path: None,
                     url: None,
                     version: Some("1.0.0".to_string()),
+                    constraint: None,
+                    integrity: None,
                 })
                 .collect(),
         }
@@ -0,0 +1,555 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+use serde::Deserialize;
+
+use super::dependencies::Dependancies;
+use super::haxelib::{installed_path_for_name, Haxelib, HaxelibType};
+
+/// The parts of a library's own `haxelib.json` that matter for dependency resolution and exports
+#[derive(Deserialize, Default, Debug)]
+pub struct HaxelibJson {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    /// Where the sources live relative to haxelib.json, used by `export lix`
+    #[serde(default, rename = "classPath")]
+    pub class_path: Option<String>,
+}
+
+/// Reads `<lib_root>/haxelib.json`, or None if the library doesn't have one
+pub fn read_haxelib_json(lib_root: &Path) -> Result<Option<HaxelibJson>> {
+    let path = lib_root.join("haxelib.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = File::open(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let json = serde_json::from_reader(file)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(json))
+}
+
+/// What a library asks for in the `dependencies` of its haxelib.json
+#[derive(Debug, Clone, PartialEq)]
+pub enum Requirement {
+    /// `""`, any version will do
+    Any,
+    /// A haxelib version, e.g. `"5.2.0"`
+    Version(String),
+    /// `"git:<url>[#ref]"` or `"hg:<url>[#ref]"`
+    Vcs {
+        haxelib_type: HaxelibType,
+        url: String,
+        vcs_ref: Option<String>,
+    },
+}
+
+impl Requirement {
+    pub fn parse(spec: &str) -> Self {
+        let spec = spec.trim();
+        let vcs = [("git:", HaxelibType::Git), ("hg:", HaxelibType::Mecurial)]
+            .into_iter()
+            .find_map(|(prefix, haxelib_type)| {
+                spec.strip_prefix(prefix).map(|rest| (haxelib_type, rest))
+            });
+
+        match vcs {
+            Some((haxelib_type, rest)) => {
+                let (url, vcs_ref) = match rest.split_once('#') {
+                    Some((url, r)) if !r.is_empty() => (url, Some(r.to_string())),
+                    Some((url, _)) => (url, None),
+                    None => (rest, None),
+                };
+                Requirement::Vcs {
+                    haxelib_type,
+                    url: url.to_string(),
+                    vcs_ref,
+                }
+            }
+            None if spec.is_empty() => Requirement::Any,
+            None => Requirement::Version(spec.to_string()),
+        }
+    }
+
+    /// The requirement as written in haxelib.json
+    pub fn spec(&self) -> String {
+        match self {
+            Requirement::Any => String::new(),
+            Requirement::Version(version) => version.clone(),
+            Requirement::Vcs {
+                haxelib_type,
+                url,
+                vcs_ref,
+            } => {
+                let vcs = match haxelib_type {
+                    HaxelibType::Mecurial => "hg",
+                    _ => "git",
+                };
+                match vcs_ref {
+                    Some(r) => format!("{}:{}#{}", vcs, url, r),
+                    None => format!("{}:{}", vcs, url),
+                }
+            }
+        }
+    }
+
+    /// The hmm.json entry that satisfies this requirement. `Any` has no version yet,
+    /// callers resolve it to the latest release.
+    pub fn to_haxelib(&self, name: &str) -> Haxelib {
+        let mut haxelib = Haxelib {
+            name: name.to_string(),
+            haxelib_type: HaxelibType::Haxelib,
+            dir: None,
+            vcs_ref: None,
+            path: None,
+            url: None,
+            version: None,
+            constraint: None,
+            integrity: None,
+        };
+        match self {
+            Requirement::Any => {}
+            Requirement::Version(version) => haxelib.version = Some(version.clone()),
+            Requirement::Vcs {
+                haxelib_type,
+                url,
+                vcs_ref,
+            } => {
+                haxelib.haxelib_type = haxelib_type.clone();
+                haxelib.url = Some(url.clone());
+                haxelib.vcs_ref = vcs_ref.clone();
+            }
+        }
+        haxelib
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Requirement::Any => write!(f, "any version"),
+            Requirement::Version(version) => write!(f, "{}", version),
+            Requirement::Vcs {
+                haxelib_type,
+                url,
+                vcs_ref: Some(r),
+            } => write!(f, "{:?} {}#{}", haxelib_type, url, r),
+            Requirement::Vcs {
+                haxelib_type, url, ..
+            } => write!(f, "{:?} {}", haxelib_type, url),
+        }
+    }
+}
+
+pub struct GraphNode {
+    pub name: String,
+    /// Listed in hmm.json, rather than only pulled in by another library
+    pub pinned: bool,
+    /// Where the library is installed in `.haxelib/`, if it is
+    pub path: Option<PathBuf>,
+    /// The version from the installed haxelib.json
+    pub installed_version: Option<String>,
+    pub dependencies: Vec<(String, Requirement)>,
+}
+
+/// Every library reachable from hmm.json, following the `dependencies` of installed libraries
+pub struct DependencyGraph {
+    /// hmm.json dependencies, in hmm.json order
+    pub roots: Vec<String>,
+    pub nodes: BTreeMap<String, GraphNode>,
+    /// Installed libraries whose haxelib.json couldn't be read, so their dependencies are missing
+    pub unreadable: Vec<(String, anyhow::Error)>,
+}
+
+impl DependencyGraph {
+    /// A library with an unreadable haxelib.json is kept as a node without dependencies,
+    /// and listed in `unreadable`
+    pub fn build(deps: &Dependancies) -> Self {
+        let roots: Vec<String> = deps.dependencies.iter().map(|d| d.name.clone()).collect();
+        let mut nodes = BTreeMap::new();
+        let mut unreadable = Vec::new();
+        let mut queue: VecDeque<String> = roots.iter().cloned().collect();
+
+        while let Some(name) = queue.pop_front() {
+            if nodes.contains_key(&name) {
+                continue;
+            }
+
+            let path = installed_path_for_name(&name);
+            let haxelib_json = match path.as_deref().map(read_haxelib_json) {
+                Some(Ok(json)) => json,
+                Some(Err(e)) => {
+                    unreadable.push((name.clone(), e));
+                    None
+                }
+                None => None,
+            };
+            let (installed_version, dependencies) = match haxelib_json {
+                Some(json) => (
+                    json.version,
+                    json.dependencies
+                        .iter()
+                        .map(|(dep, spec)| (dep.clone(), Requirement::parse(spec)))
+                        .collect::<Vec<_>>(),
+                ),
+                None => (None, Vec::new()),
+            };
+
+            for (dep, _) in dependencies.iter() {
+                if !nodes.contains_key(dep) {
+                    queue.push_back(dep.clone());
+                }
+            }
+
+            nodes.insert(
+                name.clone(),
+                GraphNode {
+                    pinned: roots.contains(&name),
+                    name,
+                    path,
+                    installed_version,
+                    dependencies,
+                },
+            );
+        }
+
+        Self {
+            roots,
+            nodes,
+            unreadable,
+        }
+    }
+
+    /// Prints a warning for every library in `unreadable`
+    pub fn warn_unreadable(&self) {
+        for (name, e) in self.unreadable.iter() {
+            eprintln!(
+                "{}: ignoring the dependencies of {}, {:#}",
+                "Warning".yellow(),
+                name.yellow(),
+                e
+            );
+        }
+    }
+
+    /// Libraries that depend on `name`, with what they ask for
+    pub fn dependents(&self, name: &str) -> Vec<(&str, &Requirement)> {
+        self.nodes
+            .values()
+            .flat_map(|node| {
+                node.dependencies
+                    .iter()
+                    .filter(|(dep, _)| dep == name)
+                    .map(|(_, requirement)| (node.name.as_str(), requirement))
+            })
+            .collect()
+    }
+
+    /// Every path from an hmm.json dependency down to `name`, without going around cycles
+    pub fn paths_to(&self, name: &str) -> Vec<Vec<String>> {
+        let mut paths = Vec::new();
+        for root in self.roots.iter() {
+            let mut path = vec![root.clone()];
+            self.collect_paths(name, &mut path, &mut paths);
+        }
+        paths
+    }
+
+    fn collect_paths(&self, target: &str, path: &mut Vec<String>, paths: &mut Vec<Vec<String>>) {
+        let current = path.last().expect("paths are never empty");
+        if current == target {
+            paths.push(path.clone());
+            return;
+        }
+        let Some(node) = self.nodes.get(current) else {
+            return;
+        };
+        for (dep, _) in node.dependencies.iter() {
+            if path.contains(dep) {
+                continue;
+            }
+            path.push(dep.clone());
+            self.collect_paths(target, path, paths);
+            path.pop();
+        }
+    }
+
+    /// Transitive dependencies that hmm.json doesn't list
+    pub fn unpinned(&self) -> Vec<&GraphNode> {
+        self.nodes.values().filter(|node| !node.pinned).collect()
+    }
+
+    /// Libraries whose haxelib.json version requirements don't agree with the version hmm.json
+    /// pins (or that is installed), or with each other. Git / hg / dev pins are left alone,
+    /// since they deliberately override whatever version other libraries ask for.
+    pub fn conflicts(&self, deps: &Dependancies) -> Vec<VersionConflict> {
+        let mut conflicts = Vec::new();
+
+        for node in self.nodes.values() {
+            let requirements: Vec<(String, String)> = self
+                .dependents(&node.name)
+                .into_iter()
+                .filter_map(|(by, requirement)| match requirement {
+                    Requirement::Version(v) => Some((by.to_string(), v.clone())),
+                    _ => None,
+                })
+                .collect();
+            if requirements.is_empty() {
+                continue;
+            }
+
+            let resolved = match deps.get_haxelib(&node.name) {
+                Ok(lib) if lib.haxelib_type != HaxelibType::Haxelib => continue,
+                Ok(lib) => lib.version.clone().map(|v| (v, ConflictSource::HmmJson)),
+                Err(_) => None,
+            }
+            .or_else(|| {
+                node.installed_version
+                    .clone()
+                    .map(|v| (v, ConflictSource::Installed))
+            });
+
+            let disagrees = match &resolved {
+                Some((version, _)) => requirements
+                    .iter()
+                    .any(|(_, wanted)| !version_satisfies(version, wanted)),
+                // Nothing to compare against, but the requirements can still exclude each other
+                None => requirements.iter().any(|(_, a)| {
+                    requirements
+                        .iter()
+                        .any(|(_, b)| !version_satisfies(a, b) && !version_satisfies(b, a))
+                }),
+            };
+
+            if disagrees {
+                conflicts.push(VersionConflict {
+                    name: node.name.clone(),
+                    resolved,
+                    requirements,
+                });
+            }
+        }
+
+        conflicts
+    }
+}
+
+/// Where the version a conflict was checked against comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictSource {
+    HmmJson,
+    Installed,
+}
+
+impl fmt::Display for ConflictSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConflictSource::HmmJson => write!(f, "hmm.json pins"),
+            ConflictSource::Installed => write!(f, "installed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VersionConflict {
+    pub name: String,
+    /// The version in use, and where it comes from
+    pub resolved: Option<(String, ConflictSource)>,
+    /// Every library asking for a specific version, with the version it asks for
+    pub requirements: Vec<(String, String)>,
+}
+
+/// Whether `version` satisfies a haxelib.json requirement for `wanted`. Requirements are
+/// treated like caret ranges (`8.0.0` accepts any `8.x` at or above it), other strings must
+/// match exactly.
+pub fn version_satisfies(version: &str, wanted: &str) -> bool {
+    match (
+        semver::Version::parse(version),
+        semver::VersionReq::parse(&format!("^{}", wanted)),
+    ) {
+        (Ok(version), Ok(req)) => req.matches(&version),
+        _ => version == wanted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requirement_any() {
+        assert_eq!(Requirement::parse(""), Requirement::Any);
+        assert_eq!(Requirement::parse("  "), Requirement::Any);
+    }
+
+    #[test]
+    fn test_parse_requirement_version() {
+        assert_eq!(
+            Requirement::parse("5.2.0"),
+            Requirement::Version("5.2.0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_requirement_git_with_ref() {
+        assert_eq!(
+            Requirement::parse("git:https://github.com/HaxeFoundation/hxcpp#v4.3.68"),
+            Requirement::Vcs {
+                haxelib_type: HaxelibType::Git,
+                url: "https://github.com/HaxeFoundation/hxcpp".to_string(),
+                vcs_ref: Some("v4.3.68".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_requirement_hg_without_ref() {
+        assert_eq!(
+            Requirement::parse("hg:https://hg.example.com/lib"),
+            Requirement::Vcs {
+                haxelib_type: HaxelibType::Mecurial,
+                url: "https://hg.example.com/lib".to_string(),
+                vcs_ref: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_requirement_spec_round_trips() {
+        for spec in [
+            "",
+            "5.2.0",
+            "git:https://example.com/repo#main",
+            "hg:https://example.com/repo",
+        ] {
+            assert_eq!(Requirement::parse(spec).spec(), spec);
+        }
+    }
+
+    #[test]
+    fn test_requirement_to_haxelib() {
+        let lib = Requirement::Version("1.0.0".to_string()).to_haxelib("lime");
+        assert_eq!(lib.haxelib_type, HaxelibType::Haxelib);
+        assert_eq!(lib.try_version(), Some("1.0.0"));
+
+        let lib = Requirement::parse("git:https://example.com/repo#main").to_haxelib("repo");
+        assert_eq!(lib.haxelib_type, HaxelibType::Git);
+        assert_eq!(lib.try_url(), Some("https://example.com/repo"));
+        assert_eq!(lib.try_vcs_ref(), Some("main"));
+    }
+
+    fn node(name: &str, pinned: bool, dependencies: &[&str]) -> (String, GraphNode) {
+        let node = GraphNode {
+            name: name.to_string(),
+            pinned,
+            path: None,
+            installed_version: None,
+            dependencies: dependencies
+                .iter()
+                .map(|d| (d.to_string(), Requirement::Any))
+                .collect(),
+        };
+        (name.to_string(), node)
+    }
+
+    #[test]
+    fn test_paths_to_follows_every_route_and_skips_cycles() {
+        let graph = DependencyGraph {
+            roots: vec!["flixel".to_string(), "lime".to_string()],
+            nodes: BTreeMap::from([
+                node("flixel", true, &["openfl", "lime"]),
+                node("openfl", false, &["lime", "flixel"]),
+                node("lime", true, &[]),
+            ]),
+            unreadable: Vec::new(),
+        };
+
+        assert_eq!(
+            graph.paths_to("lime"),
+            vec![
+                vec!["flixel", "openfl", "lime"],
+                vec!["flixel", "lime"],
+                vec!["lime"],
+            ]
+        );
+        assert_eq!(graph.dependents("lime").len(), 2);
+        assert!(graph.unpinned().iter().all(|n| n.name == "openfl"));
+    }
+
+    #[test]
+    fn test_version_satisfies_is_caret_compatible() {
+        assert!(version_satisfies("8.1.2", "8.0.0"));
+        assert!(version_satisfies("8.0.0", "8.0.0"));
+        assert!(!version_satisfies("7.9.0", "8.0.0"));
+        assert!(!version_satisfies("9.0.0", "8.0.0"));
+        assert!(!version_satisfies("0.3.0", "0.2.0"));
+        assert!(version_satisfies("not-semver", "not-semver"));
+        assert!(!version_satisfies("not-semver", "1.0.0"));
+    }
+
+    #[test]
+    fn test_conflicts_between_requirements() {
+        let mut graph = DependencyGraph {
+            roots: vec!["flixel".to_string(), "lime".to_string()],
+            nodes: BTreeMap::from([
+                node("flixel", true, &[]),
+                node("openfl", false, &[]),
+                node("lime", true, &[]),
+            ]),
+            unreadable: Vec::new(),
+        };
+        let wants = |v: &str| ("lime".to_string(), Requirement::Version(v.to_string()));
+        graph.nodes.get_mut("flixel").unwrap().dependencies = vec![wants("8.0.0")];
+
+        let mut deps = Dependancies {
+            dependencies: vec![Requirement::Version("7.9.0".to_string()).to_haxelib("lime")],
+        };
+        let conflicts = graph.conflicts(&deps);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].name, "lime");
+        assert_eq!(
+            conflicts[0].resolved,
+            Some(("7.9.0".to_string(), ConflictSource::HmmJson))
+        );
+        assert_eq!(
+            conflicts[0].requirements,
+            vec![("flixel".to_string(), "8.0.0".to_string())]
+        );
+
+        deps.dependencies[0].version = Some("8.1.0".to_string());
+        assert!(graph.conflicts(&deps).is_empty());
+
+        // git pins override requirements
+        deps.dependencies[0] =
+            Requirement::parse("git:https://example.com/lime").to_haxelib("lime");
+        assert!(graph.conflicts(&deps).is_empty());
+
+        // without a pin, requirements that exclude each other still conflict
+        deps.dependencies.clear();
+        graph.nodes.get_mut("openfl").unwrap().dependencies = vec![wants("7.0.0")];
+        assert_eq!(graph.conflicts(&deps).len(), 1);
+    }
+
+    #[test]
+    fn test_read_haxelib_json() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(read_haxelib_json(temp.path()).unwrap().is_none());
+
+        std::fs::write(
+            temp.path().join("haxelib.json"),
+            r#"{"name": "flixel", "version": "5.6.0", "dependencies": {"lime": "", "openfl": "9.3.0"}}"#,
+        )
+        .unwrap();
+        let json = read_haxelib_json(temp.path()).unwrap().unwrap();
+        assert_eq!(json.version.as_deref(), Some("5.6.0"));
+        assert_eq!(json.dependencies.len(), 2);
+        assert_eq!(json.dependencies["openfl"], "9.3.0");
+    }
+}
@@ -0,0 +1,631 @@
+//! Haxe's own serialization format (`haxe.Serializer` / `haxe.Unserializer`),
+//! which lib.haxe.org speaks over Haxe remoting.
+//! See <https://haxe.org/manual/std-serialization-format.html>
+
+use std::fmt;
+
+use anyhow::{anyhow, Context, Result};
+
+/// A serialized Haxe value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i32),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// `haxe.ds.List`
+    List(Vec<Value>),
+    /// An anonymous structure, fields in serialization order
+    Object(Vec<(String, Value)>),
+    /// `haxe.ds.StringMap`
+    StringMap(Vec<(String, Value)>),
+    /// `haxe.ds.IntMap`
+    IntMap(Vec<(i32, Value)>),
+    /// Either `YYYY-MM-DD hh:mm:ss` (Haxe 3) or milliseconds since the epoch (Haxe 4)
+    Date(String),
+    /// A class instance
+    Class {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    /// An enum value, serialized with its constructor name
+    Enum {
+        name: String,
+        constructor: String,
+        args: Vec<Value>,
+    },
+    /// An enum value, serialized with its constructor index (`Serializer.USE_ENUM_INDEX`)
+    EnumIndex {
+        name: String,
+        index: usize,
+        args: Vec<Value>,
+    },
+    /// A thrown value, how remoting reports errors
+    Exception(Box<Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The items of an Array or a List
+    pub fn as_slice(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) | Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// A field of an anonymous structure or class instance
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) | Value::Class { fields, .. } => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// Prints strings as they are and anything else in Haxe-ish notation, for error messages
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::String(s) | Value::Date(s) => write!(f, "{}", s),
+            Value::Array(items) | Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Value::Object(fields) | Value::Class { fields, .. } | Value::StringMap(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+            Value::IntMap(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+            Value::Enum {
+                constructor, args, ..
+            } => write_enum(f, constructor, args),
+            Value::EnumIndex { index, args, .. } => write_enum(f, &index.to_string(), args),
+            Value::Exception(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+fn write_enum(f: &mut fmt::Formatter, constructor: &str, args: &[Value]) -> fmt::Result {
+    if args.is_empty() {
+        return write!(f, "{}", constructor);
+    }
+    let args: Vec<String> = args.iter().map(|v| v.to_string()).collect();
+    write!(f, "{}({})", constructor, args.join(", "))
+}
+
+/// Serializes `value` the way `haxe.Serializer` does, without the string and object caches
+pub fn serialize(value: &Value) -> String {
+    let mut out = String::new();
+    serialize_into(value, &mut out);
+    out
+}
+
+fn serialize_into(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push('n'),
+        Value::Bool(true) => out.push('t'),
+        Value::Bool(false) => out.push('f'),
+        Value::Int(0) => out.push('z'),
+        Value::Int(i) => out.push_str(&format!("i{}", i)),
+        Value::Float(x) if x.is_nan() => out.push('k'),
+        Value::Float(x) if x.is_infinite() => out.push(if *x > 0.0 { 'p' } else { 'm' }),
+        Value::Float(x) => out.push_str(&format!("d{}", x)),
+        Value::String(s) => serialize_string(s, out),
+        Value::Array(items) => {
+            out.push('a');
+            let mut nulls = 0;
+            for item in items {
+                if *item == Value::Null {
+                    nulls += 1;
+                    continue;
+                }
+                push_nulls(nulls, out);
+                nulls = 0;
+                serialize_into(item, out);
+            }
+            push_nulls(nulls, out);
+            out.push('h');
+        }
+        Value::List(items) => {
+            out.push('l');
+            items.iter().for_each(|item| serialize_into(item, out));
+            out.push('h');
+        }
+        Value::Object(fields) => {
+            out.push('o');
+            serialize_fields(fields, out);
+            out.push('g');
+        }
+        Value::StringMap(fields) => {
+            out.push('b');
+            serialize_fields(fields, out);
+            out.push('h');
+        }
+        Value::IntMap(fields) => {
+            out.push('q');
+            for (key, value) in fields {
+                out.push_str(&format!(":{}", key));
+                serialize_into(value, out);
+            }
+            out.push('h');
+        }
+        Value::Date(date) => out.push_str(&format!("v{}", date)),
+        Value::Class { name, fields } => {
+            out.push('c');
+            serialize_string(name, out);
+            serialize_fields(fields, out);
+            out.push('g');
+        }
+        Value::Enum {
+            name,
+            constructor,
+            args,
+        } => {
+            out.push('w');
+            serialize_string(name, out);
+            serialize_string(constructor, out);
+            out.push_str(&format!(":{}", args.len()));
+            args.iter().for_each(|arg| serialize_into(arg, out));
+        }
+        Value::EnumIndex { name, index, args } => {
+            out.push('j');
+            serialize_string(name, out);
+            out.push_str(&format!(":{}:{}", index, args.len()));
+            args.iter().for_each(|arg| serialize_into(arg, out));
+        }
+        Value::Exception(e) => {
+            out.push('x');
+            serialize_into(e, out);
+        }
+    }
+}
+
+fn serialize_string(s: &str, out: &mut String) {
+    let encoded = urlencoding::encode(s);
+    out.push_str(&format!("y{}:{}", encoded.len(), encoded));
+}
+
+fn serialize_fields(fields: &[(String, Value)], out: &mut String) {
+    for (key, value) in fields {
+        serialize_string(key, out);
+        serialize_into(value, out);
+    }
+}
+
+fn push_nulls(count: usize, out: &mut String) {
+    match count {
+        0 => {}
+        1 => out.push('n'),
+        n => out.push_str(&format!("u{}", n)),
+    }
+}
+
+/// Reads a single value serialized by `haxe.Serializer`, including string (`R`) and
+/// object (`r`) cache references. Custom (`C`) values and bytes aren't supported
+pub fn unserialize(input: &str) -> Result<Value> {
+    let mut unserializer = Unserializer {
+        buf: input.as_bytes(),
+        pos: 0,
+        strings: Vec::new(),
+        cache: Vec::new(),
+    };
+    let value = unserializer
+        .read_value()
+        .with_context(|| format!("Invalid Haxe serialized data: {}", truncate(input)))?;
+    Ok(value)
+}
+
+/// The first 200 characters of `input`, for error messages
+pub fn truncate(input: &str) -> &str {
+    match input.char_indices().nth(200) {
+        Some((i, _)) => &input[..i],
+        None => input,
+    }
+}
+
+struct Unserializer<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    strings: Vec<String>,
+    /// Arrays, lists, objects, maps, dates, class instances and enums, for `r` references.
+    /// Containers take their slot before their contents are read, like Haxe does
+    cache: Vec<Value>,
+}
+
+impl Unserializer<'_> {
+    fn next(&mut self) -> Result<u8> {
+        let c = *self
+            .buf
+            .get(self.pos)
+            .ok_or_else(|| anyhow!("unexpected end of input"))?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.buf.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        let c = self.next()?;
+        if c != expected {
+            return Err(anyhow!(
+                "expected '{}' at {}, found '{}'",
+                expected as char,
+                self.pos - 1,
+                c as char
+            ));
+        }
+        Ok(())
+    }
+
+    fn read_int(&mut self) -> Result<i64> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.buf[start..self.pos])?
+            .parse()
+            .map_err(|_| anyhow!("expected a number at {}", start))
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        usize::try_from(self.read_int()?).map_err(|_| anyhow!("negative length at {}", self.pos))
+    }
+
+    fn read_float_str(&mut self) -> Result<&str> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || b"+-.eE".contains(&c))
+        {
+            self.pos += 1;
+        }
+        Ok(std::str::from_utf8(&self.buf[start..self.pos])?)
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        match self.next()? {
+            b'y' => {
+                let len = self.read_len()?;
+                self.expect(b':')?;
+                let end = self.pos + len;
+                let encoded = self
+                    .buf
+                    .get(self.pos..end)
+                    .ok_or_else(|| anyhow!("string at {} runs past the end", self.pos))?;
+                self.pos = end;
+                let s = urlencoding::decode(std::str::from_utf8(encoded)?)?.into_owned();
+                self.strings.push(s.clone());
+                Ok(s)
+            }
+            b'R' => {
+                let index = self.read_len()?;
+                self.strings
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("invalid string reference {}", index))
+            }
+            c => Err(anyhow!(
+                "expected a string at {}, found '{}'",
+                self.pos - 1,
+                c as char
+            )),
+        }
+    }
+
+    /// Reads `key value` pairs until `end`
+    fn read_fields(&mut self, end: u8) -> Result<Vec<(String, Value)>> {
+        let mut fields = Vec::new();
+        while self.peek() != Some(end) {
+            let key = self.read_string()?;
+            fields.push((key, self.read_value()?));
+        }
+        self.pos += 1;
+        Ok(fields)
+    }
+
+    fn read_items(&mut self) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'h') => {
+                    self.pos += 1;
+                    return Ok(items);
+                }
+                Some(b'u') => {
+                    self.pos += 1;
+                    let nulls = self.read_len()?;
+                    items.extend(std::iter::repeat_n(Value::Null, nulls));
+                }
+                _ => items.push(self.read_value()?),
+            }
+        }
+    }
+
+    fn read_args(&mut self) -> Result<Vec<Value>> {
+        self.expect(b':')?;
+        let count = self.read_len()?;
+        (0..count).map(|_| self.read_value()).collect()
+    }
+
+    /// Reserves a cache slot for a container, filled in once it's complete
+    fn reserve(&mut self) -> usize {
+        self.cache.push(Value::Null);
+        self.cache.len() - 1
+    }
+
+    fn cached(&mut self, slot: usize, value: Value) -> Value {
+        self.cache[slot] = value.clone();
+        value
+    }
+
+    fn read_value(&mut self) -> Result<Value> {
+        let c = self.next()?;
+        let value = match c {
+            b'n' => Value::Null,
+            b't' => Value::Bool(true),
+            b'f' => Value::Bool(false),
+            b'z' => Value::Int(0),
+            b'i' => {
+                let i = self.read_int()?;
+                Value::Int(i32::try_from(i).map_err(|_| anyhow!("int {} out of range", i))?)
+            }
+            b'd' => {
+                let s = self.read_float_str()?;
+                Value::Float(s.parse().map_err(|_| anyhow!("invalid float {}", s))?)
+            }
+            b'k' => Value::Float(f64::NAN),
+            b'm' => Value::Float(f64::NEG_INFINITY),
+            b'p' => Value::Float(f64::INFINITY),
+            b'y' | b'R' => {
+                self.pos -= 1;
+                Value::String(self.read_string()?)
+            }
+            b'a' => {
+                let slot = self.reserve();
+                let items = self.read_items()?;
+                self.cached(slot, Value::Array(items))
+            }
+            b'l' => {
+                let slot = self.reserve();
+                let items = self.read_items()?;
+                self.cached(slot, Value::List(items))
+            }
+            b'o' => {
+                let slot = self.reserve();
+                let fields = self.read_fields(b'g')?;
+                self.cached(slot, Value::Object(fields))
+            }
+            b'b' => {
+                let slot = self.reserve();
+                let fields = self.read_fields(b'h')?;
+                self.cached(slot, Value::StringMap(fields))
+            }
+            b'q' => {
+                let slot = self.reserve();
+                let mut fields = Vec::new();
+                while self.peek() == Some(b':') {
+                    self.pos += 1;
+                    let key = self.read_int()?;
+                    let key =
+                        i32::try_from(key).map_err(|_| anyhow!("key {} out of range", key))?;
+                    fields.push((key, self.read_value()?));
+                }
+                self.expect(b'h')?;
+                self.cached(slot, Value::IntMap(fields))
+            }
+            b'v' => {
+                // Haxe 3 wrote `YYYY-MM-DD hh:mm:ss`, Haxe 4 writes a float timestamp
+                let date = match self.buf.get(self.pos..self.pos + 19) {
+                    Some(s) if s[4] == b'-' && s[7] == b'-' && s[13] == b':' => {
+                        self.pos += 19;
+                        std::str::from_utf8(s)?.to_string()
+                    }
+                    _ => self.read_float_str()?.to_string(),
+                };
+                let slot = self.reserve();
+                self.cached(slot, Value::Date(date))
+            }
+            b'c' => {
+                let slot = self.reserve();
+                let name = self.read_string()?;
+                let fields = self.read_fields(b'g')?;
+                self.cached(slot, Value::Class { name, fields })
+            }
+            b'w' => {
+                let name = self.read_string()?;
+                let constructor = self.read_string()?;
+                let args = self.read_args()?;
+                let slot = self.reserve();
+                self.cached(
+                    slot,
+                    Value::Enum {
+                        name,
+                        constructor,
+                        args,
+                    },
+                )
+            }
+            b'j' => {
+                let name = self.read_string()?;
+                self.expect(b':')?;
+                let index = self.read_len()?;
+                let args = self.read_args()?;
+                let slot = self.reserve();
+                self.cached(slot, Value::EnumIndex { name, index, args })
+            }
+            b'r' => {
+                let index = self.read_len()?;
+                self.cache
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("invalid object reference {}", index))?
+            }
+            b'x' => Value::Exception(Box::new(self.read_value()?)),
+            c => {
+                return Err(anyhow!(
+                    "unsupported type '{}' at {}",
+                    c as char,
+                    self.pos - 1
+                ))
+            }
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn test_serialize_remoting_call() {
+        let path = Value::Array(vec![string("api"), string("getLatestVersion")]);
+        let params = Value::Array(vec![string("lime")]);
+        assert_eq!(
+            serialize(&path) + &serialize(&params),
+            "ay3:apiy16:getLatestVersionhay4:limeh"
+        );
+    }
+
+    #[test]
+    fn test_serialize_scalars() {
+        assert_eq!(serialize(&Value::Null), "n");
+        assert_eq!(serialize(&Value::Int(0)), "z");
+        assert_eq!(serialize(&Value::Int(-12)), "i-12");
+        assert_eq!(serialize(&Value::Float(1.5)), "d1.5");
+        assert_eq!(serialize(&string("a b")), "y5:a%20b");
+        assert_eq!(
+            serialize(&Value::Array(vec![Value::Null, Value::Null, Value::Int(1)])),
+            "au2i1h"
+        );
+    }
+
+    #[test]
+    fn test_unserialize_object_and_list() {
+        let value = unserialize("loy2:idi5y4:namey6:flixelgoR0i6R1y9:flixel-uigh").unwrap();
+        let items = value.as_slice().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].field("id").and_then(Value::as_int), Some(5));
+        assert_eq!(
+            items[1].field("name").and_then(Value::as_str),
+            Some("flixel-ui")
+        );
+    }
+
+    #[test]
+    fn test_unserialize_enums_and_exceptions() {
+        assert_eq!(
+            unserialize("wy5:Colory3:Rgb:3i255zz").unwrap(),
+            Value::Enum {
+                name: "Color".to_string(),
+                constructor: "Rgb".to_string(),
+                args: vec![Value::Int(255), Value::Int(0), Value::Int(0)],
+            }
+        );
+        assert_eq!(
+            unserialize("jy5:Color:1:0").unwrap(),
+            Value::EnumIndex {
+                name: "Color".to_string(),
+                index: 1,
+                args: vec![],
+            }
+        );
+        let e = unserialize("xy32:No%20such%20Project%20%3A%20nope").unwrap();
+        assert_eq!(
+            e,
+            Value::Exception(Box::new(string("No such Project : nope")))
+        );
+        assert_eq!(e.to_string(), "No such Project : nope");
+    }
+
+    #[test]
+    fn test_unserialize_dates_and_references() {
+        assert_eq!(
+            unserialize("v2023-01-02 03:04:05").unwrap(),
+            Value::Date("2023-01-02 03:04:05".to_string())
+        );
+        assert_eq!(
+            unserialize("v1672628645000").unwrap(),
+            Value::Date("1672628645000".to_string())
+        );
+        assert_eq!(
+            unserialize("aoy1:xi1gr1h").unwrap(),
+            Value::Array(vec![
+                Value::Object(vec![("x".to_string(), Value::Int(1))]),
+                Value::Object(vec![("x".to_string(), Value::Int(1))]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = Value::Object(vec![
+            ("name".to_string(), string("hxcpp")),
+            ("tags".to_string(), Value::List(vec![string("cpp")])),
+            (
+                "maps".to_string(),
+                Value::Array(vec![
+                    Value::StringMap(vec![("k".to_string(), Value::Bool(true))]),
+                    Value::IntMap(vec![(3, Value::Float(f64::INFINITY))]),
+                ]),
+            ),
+            (
+                "class".to_string(),
+                Value::Class {
+                    name: "haxe.Foo".to_string(),
+                    fields: vec![("a".to_string(), Value::Null)],
+                },
+            ),
+        ]);
+        assert_eq!(unserialize(&serialize(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn test_unserialize_errors() {
+        assert!(unserialize("y10:short").is_err());
+        assert!(unserialize("R0").is_err());
+        assert!(unserialize("ai1").is_err());
+        assert!(unserialize("C").is_err());
+    }
+}
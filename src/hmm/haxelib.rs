@@ -17,6 +17,13 @@ pub struct Haxelib {
     pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    /// A semver range such as `^5.2` or `>=8.0.0 <9`. `install` and `update` resolve it against
+    /// the releases on lib.haxe.org and record the result in `version`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<String>,
+    /// sha256 of the haxelib zip, or the git tree hash of the locked commit. Filled by `hmm lock`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
 }
 
 impl Haxelib {
@@ -53,7 +60,9 @@ impl Haxelib {
         self.url.as_deref()
     }
 
-    pub fn download_url(&self) -> Result<String> {
+    /// Where to get this library from. Haxelibs are downloaded from `server`, a haxelib
+    /// server base url like `https://lib.haxe.org`
+    pub fn download_url(&self, server: &str) -> Result<String> {
         match self.haxelib_type {
             HaxelibType::Haxelib => {
                 let version = self.try_version().ok_or_else(|| {
@@ -63,15 +72,12 @@ impl Haxelib {
                         self.name
                     )
                 })?;
-                Ok(format!(
-                    "https://lib.haxe.org/p/{}/{}/download",
-                    self.name, version
-                ))
+                Ok(format!("{}/p/{}/{}/download", server, self.name, version))
             }
-            HaxelibType::Git => {
-                let url = self
-                    .try_url()
-                    .ok_or_else(|| anyhow!("{}: url required for Git", self.name))?;
+            HaxelibType::Git | HaxelibType::Mecurial => {
+                let url = self.try_url().ok_or_else(|| {
+                    anyhow!("{}: url required for {:?}", self.name, self.haxelib_type)
+                })?;
                 Ok(url.to_string())
             }
             _ => Err(anyhow!(
@@ -90,6 +96,9 @@ impl Haxelib {
             HaxelibType::Git => self
                 .try_vcs_ref()
                 .ok_or_else(|| anyhow!("{}: Git requires vcs_ref", self.name)),
+            HaxelibType::Mecurial => self
+                .try_vcs_ref()
+                .ok_or_else(|| anyhow!("{}: Mercurial requires vcs_ref", self.name)),
             _ => Err(anyhow!(
                 "{}: Unsupported type {:?}",
                 self.name,
@@ -115,6 +124,11 @@ impl Haxelib {
     pub fn git_repo_path(&self) -> PathBuf {
         self.lib_dir_path().join("git")
     }
+
+    /// Returns the mercurial repo path: .haxelib/{name_with_commas}/hg
+    pub fn hg_repo_path(&self) -> PathBuf {
+        self.lib_dir_path().join("hg")
+    }
 }
 
 /// Returns the library directory path given a library name
@@ -127,6 +141,24 @@ pub fn git_repo_path_for_name(name: &str) -> PathBuf {
     lib_dir_path_for_name(name).join("git")
 }
 
+/// Returns the directory an installed library's sources (and haxelib.json) live in,
+/// following `.dev` first, then `.current` (`git`, `hg`, or a haxelib version)
+pub fn installed_path_for_name(name: &str) -> Option<PathBuf> {
+    let lib_dir = lib_dir_path_for_name(name);
+
+    if let Ok(dev) = std::fs::read_to_string(lib_dir.join(".dev")) {
+        return Some(PathBuf::from(dev.trim()));
+    }
+
+    let current = std::fs::read_to_string(lib_dir.join(".current")).ok()?;
+    let path = match current.trim() {
+        "git" => lib_dir.join("git"),
+        "hg" => lib_dir.join("hg"),
+        version => lib_dir.join(version.replace(".", ",")),
+    };
+    path.exists().then_some(path)
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum HaxelibType {
     #[serde(rename = "git")]
@@ -158,6 +190,8 @@ mod tests {
             path: None,
             url: url.map(|s| s.to_string()),
             version: version.map(|s| s.to_string()),
+            constraint: None,
+            integrity: None,
         }
     }
 
@@ -225,9 +259,13 @@ mod tests {
     fn test_download_url_haxelib() {
         let h = make_haxelib("flixel-addons", HaxelibType::Haxelib, Some("3.3.0"), None, None);
         assert_eq!(
-            h.download_url().unwrap(),
+            h.download_url("https://lib.haxe.org").unwrap(),
             "https://lib.haxe.org/p/flixel-addons/3.3.0/download"
         );
+        assert_eq!(
+            h.download_url("http://haxelib.internal:2000").unwrap(),
+            "http://haxelib.internal:2000/p/flixel-addons/3.3.0/download"
+        );
     }
 
     #[test]
@@ -240,7 +278,7 @@ mod tests {
             Some("https://github.com/haxeflixel/flixel"),
         );
         assert_eq!(
-            h.download_url().unwrap(),
+            h.download_url("https://lib.haxe.org").unwrap(),
             "https://github.com/haxeflixel/flixel"
         );
     }
@@ -248,7 +286,7 @@ mod tests {
     #[test]
     fn test_download_url_dev_fails() {
         let h = make_haxelib("local-lib", HaxelibType::Dev, None, None, None);
-        assert!(h.download_url().is_err());
+        assert!(h.download_url("https://lib.haxe.org").is_err());
     }
 
     // --- version_or_ref ---
@@ -0,0 +1,637 @@
+//! Typed client for the lib.haxe.org remoting API (`SiteApi` in the haxelib server)
+
+use anyhow::{anyhow, Context, Result};
+use owo_colors::OwoColorize;
+use reqwest::blocking::Client;
+
+use super::haxe_serializer::{self, Value};
+
+pub const DEFAULT_HAXELIB_SERVER: &str = "https://lib.haxe.org";
+
+/// The haxelib server and its mirrors, in the order they're tried. Downloads and API calls
+/// move on to the next one when a server can't be reached or answers with a 5xx
+#[derive(Debug, Clone, PartialEq)]
+pub struct HaxelibServers {
+    urls: Vec<String>,
+}
+
+impl Default for HaxelibServers {
+    fn default() -> Self {
+        Self::new(DEFAULT_HAXELIB_SERVER, &[])
+    }
+}
+
+impl HaxelibServers {
+    /// `server` and `mirrors` are base urls, like [`DEFAULT_HAXELIB_SERVER`]
+    pub fn new(server: &str, mirrors: &[String]) -> Self {
+        let urls = std::iter::once(server)
+            .chain(mirrors.iter().map(String::as_str))
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty())
+            .collect();
+        Self { urls }
+    }
+
+    /// Resolve the servers: CLI flags > $HMM_HAXELIB_SERVER / $HMM_HAXELIB_MIRRORS (comma
+    /// separated) > lib.haxe.org without mirrors. Empty strings are treated as "not set"
+    pub fn resolve(server: Option<&str>, mirrors: &[String]) -> Self {
+        Self::resolve_from(server, mirrors, |key| std::env::var(key).ok())
+    }
+
+    /// [`resolve`](Self::resolve), looking variables up with `env`
+    fn resolve_from(
+        server: Option<&str>,
+        mirrors: &[String],
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let server = server
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .or_else(|| env("HMM_HAXELIB_SERVER"))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| DEFAULT_HAXELIB_SERVER.to_string());
+        let mirrors = match mirrors.is_empty() {
+            false => mirrors.to_vec(),
+            true => env("HMM_HAXELIB_MIRRORS")
+                .map(|m| m.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+        };
+        Self::new(&server, &mirrors)
+    }
+
+    /// Base urls, the server first
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Whether `error` means the server is down, rather than that the request was wrong
+    pub fn is_unavailable(error: &reqwest::Error) -> bool {
+        error.is_connect()
+            || error.is_timeout()
+            || error.status().is_some_and(|s| s.is_server_error())
+    }
+}
+
+/// `{ id, name }`, an entry of `search`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contributor {
+    pub name: String,
+    pub fullname: String,
+}
+
+/// A released version of a project
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionInfos {
+    /// The version itself, e.g. `5.2.0`
+    pub name: String,
+    pub date: String,
+    pub downloads: i32,
+    /// The release notes
+    pub comments: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectInfos {
+    pub name: String,
+    pub desc: String,
+    pub website: String,
+    pub owner: String,
+    pub contributors: Vec<Contributor>,
+    pub license: String,
+    pub curversion: String,
+    pub downloads: i32,
+    /// Oldest first, the way lib.haxe.org returns them
+    pub versions: Vec<VersionInfos>,
+    pub tags: Vec<String>,
+}
+
+pub struct HaxelibApi {
+    client: Client,
+    /// Remoting endpoints, tried in order
+    urls: Vec<String>,
+}
+
+impl Default for HaxelibApi {
+    fn default() -> Self {
+        Self::for_servers(&HaxelibServers::default())
+    }
+}
+
+impl HaxelibApi {
+    /// `url` is the remoting endpoint, e.g. `https://lib.haxe.org/api/3.0/index.n/`
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            urls: vec![url.to_string()],
+        }
+    }
+
+    /// Calls the remoting endpoint of each of `servers` in turn
+    pub fn for_servers(servers: &HaxelibServers) -> Self {
+        Self {
+            client: Client::new(),
+            urls: servers
+                .urls()
+                .iter()
+                .map(|server| format!("{}/api/3.0/index.n/", server))
+                .collect(),
+        }
+    }
+
+    /// Sends the calls through `proxy`, when there is one
+    pub fn with_proxy(self, proxy: Option<&str>) -> Result<Self> {
+        match proxy {
+            Some(proxy) => Ok(Self {
+                client: super::proxy::blocking_client(Some(proxy))?,
+                ..self
+            }),
+            None => Ok(self),
+        }
+    }
+
+    /// Calls `api.<method>(params...)` over Haxe remoting, turning a thrown value into an error
+    pub fn call(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+        let path = Value::Array(vec![
+            Value::String("api".to_string()),
+            Value::String(method.to_string()),
+        ]);
+        let query =
+            haxe_serializer::serialize(&path) + &haxe_serializer::serialize(&Value::Array(params));
+
+        let mut unavailable = Vec::new();
+        for url in self.urls.iter() {
+            let response = self
+                .client
+                .get(format!("{}?__x={}", url, urlencoding::encode(&query)))
+                .header("X-Haxe-Remoting", "1")
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text());
+            match response {
+                Ok(response) => return Self::decode(url, method, &response),
+                Err(e) if HaxelibServers::is_unavailable(&e) => {
+                    eprintln!("{} is unavailable: {}", url.yellow(), e);
+                    unavailable.push(url.as_str());
+                }
+                Err(e) => return Err(e).with_context(|| format!("Failed to reach {}", url)),
+            }
+        }
+        Err(anyhow!(
+            "No haxelib server could be reached (tried {})",
+            unavailable.join(", ")
+        ))
+    }
+
+    fn decode(url: &str, method: &str, response: &str) -> Result<Value> {
+        let serialized = response.strip_prefix("hxr").ok_or_else(|| {
+            anyhow!(
+                "Unexpected response from {} for {}: {}",
+                url,
+                method,
+                haxe_serializer::truncate(response)
+            )
+        })?;
+        match haxe_serializer::unserialize(serialized)? {
+            Value::Exception(e) => Err(anyhow!("{}", e)),
+            value => Ok(value),
+        }
+    }
+
+    pub fn get_latest_version(&self, project: &str) -> Result<String> {
+        let value = self.call("getLatestVersion", vec![Value::String(project.to_string())])?;
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Expected a version for {}, got {}", project, value))
+    }
+
+    pub fn infos(&self, project: &str) -> Result<ProjectInfos> {
+        let value = self.call("infos", vec![Value::String(project.to_string())])?;
+        project_infos(&value).with_context(|| format!("Unexpected infos for {}", project))
+    }
+
+    pub fn search(&self, word: &str) -> Result<Vec<SearchResult>> {
+        let value = self.call("search", vec![Value::String(word.to_string())])?;
+        items(&value, "search results")?
+            .iter()
+            .map(|item| {
+                Ok(SearchResult {
+                    id: int_field(item, "id")?,
+                    name: string_field(item, "name")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Every released version of `project`, oldest first
+    pub fn versions(&self, project: &str) -> Result<Vec<VersionInfos>> {
+        Ok(self.infos(project)?.versions)
+    }
+}
+
+fn items<'a>(value: &'a Value, what: &str) -> Result<&'a [Value]> {
+    value
+        .as_slice()
+        .ok_or_else(|| anyhow!("Expected a list of {}, got {}", what, value))
+}
+
+/// A string field, where null or missing is an empty string
+fn string_field(value: &Value, name: &str) -> Result<String> {
+    match value.field(name) {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(Value::String(s)) | Some(Value::Date(s)) => Ok(s.clone()),
+        Some(other) => Err(anyhow!("Expected {} to be a string, got {}", name, other)),
+    }
+}
+
+fn int_field(value: &Value, name: &str) -> Result<i32> {
+    match value.field(name) {
+        None | Some(Value::Null) => Ok(0),
+        Some(Value::Int(i)) => Ok(*i),
+        Some(other) => Err(anyhow!("Expected {} to be an int, got {}", name, other)),
+    }
+}
+
+fn project_infos(value: &Value) -> Result<ProjectInfos> {
+    let list = |name: &str| match value.field(name) {
+        None | Some(Value::Null) => Ok(&[][..]),
+        Some(v) => items(v, name),
+    };
+
+    Ok(ProjectInfos {
+        name: string_field(value, "name")?,
+        desc: string_field(value, "desc")?,
+        website: string_field(value, "website")?,
+        owner: string_field(value, "owner")?,
+        contributors: list("contributors")?
+            .iter()
+            .map(|c| {
+                Ok(Contributor {
+                    name: string_field(c, "name")?,
+                    fullname: string_field(c, "fullname")?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        license: string_field(value, "license")?,
+        curversion: string_field(value, "curversion")?,
+        downloads: int_field(value, "downloads")?,
+        versions: list("versions")?
+            .iter()
+            .map(|v| {
+                Ok(VersionInfos {
+                    name: string_field(v, "name")?,
+                    date: string_field(v, "date")?,
+                    downloads: int_field(v, "downloads")?,
+                    comments: string_field(v, "comments")?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        tags: list("tags")?
+            .iter()
+            .map(|t| {
+                t.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow!("Expected a tag, got {}", t))
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+/// A local stand-in for lib.haxe.org, for tests
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    use crate::hmm::haxe_serializer::{self, Value};
+
+    /// Answers every request with `respond(request line)` and sends each request head
+    /// to the receiver. Returns the remoting url to give `HaxelibApi::new`
+    pub fn serve(
+        respond: impl Fn(&str) -> String + Send + 'static,
+    ) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/3.0/index.n/", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut request = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    request.push(line.trim().to_string());
+                }
+                let body = respond(&request[0]);
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                let _ = sender.send(request.join("\n"));
+            }
+        });
+        (url, receiver)
+    }
+
+    /// A server answering every request with `status`, e.g. `503 Service Unavailable`.
+    /// Returns its remoting url
+    pub fn failing(status: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/3.0/index.n/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let mut stream = reader.into_inner();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+            }
+        });
+        url
+    }
+
+    /// The base url of a remoting url, for `HaxelibServers`
+    pub fn server(url: &str) -> &str {
+        url.trim_end_matches("/api/3.0/index.n/")
+    }
+
+    /// Whether a request line calls `api.<method>`
+    pub fn calls(request_line: &str, method: &str) -> bool {
+        let path = haxe_serializer::serialize(&Value::Array(vec![string("api"), string(method)]));
+        request_line.contains(&format!("__x={}", urlencoding::encode(&path)))
+    }
+
+    pub fn remoting_response(value: Value) -> String {
+        format!("hxr{}", haxe_serializer::serialize(&value))
+    }
+
+    pub fn object(fields: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    pub fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{object, remoting_response, string};
+    use super::*;
+    use std::sync::mpsc;
+
+    fn mock_server(body: String) -> (String, mpsc::Receiver<String>) {
+        super::mock::serve(move |_| body.clone())
+    }
+
+    #[test]
+    fn test_servers_new_trims_and_orders() {
+        let servers = HaxelibServers::new(
+            "http://haxelib.internal/",
+            &[" http://mirror-a ".to_string(), "".to_string()],
+        );
+        assert_eq!(servers.urls(), ["http://haxelib.internal", "http://mirror-a"]);
+    }
+
+    #[test]
+    fn test_servers_resolve() {
+        let unset = |_: &str| None;
+        assert_eq!(
+            HaxelibServers::resolve_from(None, &[], unset),
+            HaxelibServers::default()
+        );
+
+        let env = |key: &str| match key {
+            "HMM_HAXELIB_SERVER" => Some("http://env-server".to_string()),
+            "HMM_HAXELIB_MIRRORS" => Some("http://env-a,http://env-b".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            HaxelibServers::resolve_from(None, &[], env).urls(),
+            ["http://env-server", "http://env-a", "http://env-b"]
+        );
+        // flags win, and an empty flag counts as unset
+        assert_eq!(
+            HaxelibServers::resolve_from(Some(""), &["http://flag-mirror".to_string()], env).urls(),
+            ["http://env-server", "http://flag-mirror"]
+        );
+        assert_eq!(
+            HaxelibServers::resolve_from(Some("http://flag-server"), &[], env).urls(),
+            ["http://flag-server", "http://env-a", "http://env-b"]
+        );
+    }
+
+    #[test]
+    fn test_call_falls_back_to_mirrors() {
+        let down = super::mock::failing("503 Service Unavailable");
+        // nothing listens here once the listener is dropped
+        let closed = format!(
+            "http://{}",
+            std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+        );
+        let (up, _requests) = mock_server("hxry5:5.6.1".to_string());
+        let servers = HaxelibServers::new(
+            super::mock::server(&down),
+            &[closed, super::mock::server(&up).to_string()],
+        );
+
+        let api = HaxelibApi::for_servers(&servers);
+        assert_eq!(api.get_latest_version("flixel").unwrap(), "5.6.1");
+    }
+
+    #[test]
+    fn test_call_stops_on_client_errors() {
+        let missing = super::mock::failing("404 Not Found");
+        let (up, _requests) = mock_server("hxry5:5.6.1".to_string());
+        let servers = HaxelibServers::new(
+            super::mock::server(&missing),
+            &[super::mock::server(&up).to_string()],
+        );
+
+        let api = HaxelibApi::for_servers(&servers);
+        assert!(api.get_latest_version("flixel").is_err());
+    }
+
+    #[test]
+    fn test_get_latest_version() {
+        let (url, request) = mock_server("hxry5:5.6.1".to_string());
+        let api = HaxelibApi::new(&url);
+
+        assert_eq!(api.get_latest_version("flixel").unwrap(), "5.6.1");
+        let request = request.recv().unwrap();
+        let query = urlencoding::encode("ay3:apiy16:getLatestVersionhay6:flixelh").into_owned();
+        assert!(
+            request.starts_with(&format!("GET /api/3.0/index.n/?__x={}", query)),
+            "{}",
+            request
+        );
+        assert!(
+            request.to_lowercase().contains("x-haxe-remoting: 1"),
+            "{}",
+            request
+        );
+    }
+
+    #[test]
+    fn test_remoting_exception() {
+        let thrown = Value::Exception(Box::new(string("No such Project : x")));
+        let (url, _request) = mock_server(remoting_response(thrown));
+        let api = HaxelibApi::new(&url);
+
+        let err = api.get_latest_version("x").err().unwrap();
+        assert_eq!(err.to_string(), "No such Project : x");
+    }
+
+    #[test]
+    fn test_search() {
+        // a List of objects, with the field names coming back as string references
+        let (url, _request) =
+            mock_server("hxrloy2:idi12y4:namey6:flixelgoR0i40R1y13:flixel-addonsgh".to_string());
+        let api = HaxelibApi::new(&url);
+
+        assert_eq!(
+            api.search("flixel").unwrap(),
+            vec![
+                SearchResult {
+                    id: 12,
+                    name: "flixel".to_string()
+                },
+                SearchResult {
+                    id: 40,
+                    name: "flixel-addons".to_string()
+                },
+            ]
+        );
+    }
+
+    fn lime_infos() -> Value {
+        let version = |name: &str, date: &str, downloads: i32, comments: &str| {
+            object(vec![
+                ("date", Value::Date(date.to_string())),
+                ("name", string(name)),
+                ("downloads", Value::Int(downloads)),
+                ("comments", string(comments)),
+            ])
+        };
+        object(vec![
+            ("name", string("lime")),
+            ("desc", string("A foundational Haxe framework")),
+            ("website", Value::Null),
+            ("owner", string("openfl")),
+            (
+                "contributors",
+                Value::Array(vec![object(vec![
+                    ("name", string("openfl")),
+                    ("fullname", string("OpenFL")),
+                ])]),
+            ),
+            ("license", string("MIT")),
+            ("curversion", string("8.1.0")),
+            ("downloads", Value::Int(1000)),
+            (
+                "versions",
+                Value::Array(vec![
+                    version("8.0.0", "2023-01-02 03:04:05", 600, ""),
+                    version("8.1.0", "1686125350000", 400, "fixes"),
+                ]),
+            ),
+            ("tags", Value::List(vec![string("cross-platform")])),
+        ])
+    }
+
+    #[test]
+    fn test_infos() {
+        let (url, _request) = mock_server(remoting_response(lime_infos()));
+        let api = HaxelibApi::new(&url);
+
+        let infos = api.infos("lime").unwrap();
+        assert_eq!(infos.name, "lime");
+        assert_eq!(infos.website, "");
+        assert_eq!(infos.curversion, "8.1.0");
+        assert_eq!(infos.downloads, 1000);
+        assert_eq!(
+            infos.contributors,
+            vec![Contributor {
+                name: "openfl".to_string(),
+                fullname: "OpenFL".to_string()
+            }]
+        );
+        assert_eq!(infos.tags, vec!["cross-platform"]);
+    }
+
+    #[test]
+    fn test_versions() {
+        let (url, request) = mock_server(remoting_response(lime_infos()));
+        let api = HaxelibApi::new(&url);
+
+        let versions = api.versions("lime").unwrap();
+        assert!(request
+            .recv()
+            .unwrap()
+            .contains(&urlencoding::encode("ay3:apiy5:infoshay4:limeh").into_owned()));
+        assert_eq!(
+            versions,
+            vec![
+                VersionInfos {
+                    name: "8.0.0".to_string(),
+                    date: "2023-01-02 03:04:05".to_string(),
+                    downloads: 600,
+                    comments: String::new(),
+                },
+                VersionInfos {
+                    name: "8.1.0".to_string(),
+                    date: "1686125350000".to_string(),
+                    downloads: 400,
+                    comments: "fixes".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unexpected_response() {
+        let (url, _request) = mock_server("<html>down for maintenance</html>".to_string());
+        let api = HaxelibApi::new(&url);
+
+        let err = api.search("x").err().unwrap();
+        assert!(err.to_string().contains("Unexpected response"), "{}", err);
+    }
+
+    #[test]
+    fn test_unexpected_response_cut_inside_a_character() {
+        // 199 bytes, then a 2 byte character straddling the 200 byte mark
+        let (url, _request) = mock_server(format!("{}é{}", "a".repeat(199), "é".repeat(300)));
+        let api = HaxelibApi::new(&url);
+
+        let err = api.search("x").err().unwrap();
+        assert!(err.to_string().ends_with("aé"), "{}", err);
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+
+use super::proxy;
+
+fn hg(repo_path: &Path) -> Result<Command> {
+    let repo = repo_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid path: {}", repo_path.display()))?;
+    let mut command = Command::new("hg");
+    command.args(["--repository", repo]);
+    Ok(command)
+}
+
+/// Runs `hg` and returns its trimmed stdout, failing with its stderr
+fn run_hg(mut command: Command, what: &str) -> Result<String> {
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to execute hg {} (is Mercurial installed?)", what))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "hg {} failed: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Clones `url` into `target_path` without checking out a working copy, through `proxy` if any
+pub fn clone(url: &str, target_path: &Path, proxy: Option<&str>) -> Result<()> {
+    let target = target_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid path: {}", target_path.display()))?;
+    let mut command = Command::new("hg");
+    command
+        .args(["clone", "--noupdate", url, target])
+        .envs(proxy::command_env(proxy));
+    run_hg(command, "clone").map(|_| ())
+}
+
+/// Pulls every new changeset from the default path, through `proxy` if any
+pub fn pull(repo_path: &Path, proxy: Option<&str>) -> Result<()> {
+    let mut command = hg(repo_path)?;
+    command.arg("pull").envs(proxy::command_env(proxy));
+    run_hg(command, "pull").map(|_| ())
+}
+
+/// Updates the working copy to `rev` (a changeset id, tag, bookmark or branch)
+pub fn update(repo_path: &Path, rev: &str) -> Result<()> {
+    let mut command = hg(repo_path)?;
+    command.args(["update", "--rev", rev]);
+    run_hg(command, "update").map(|_| ())
+}
+
+/// Full changeset id `rev` resolves to, or None if the repository doesn't have it (yet)
+pub fn resolve(repo_path: &Path, rev: &str) -> Option<String> {
+    let mut command = hg(repo_path).ok()?;
+    command.args(["log", "--rev", rev, "--limit", "1", "--template", "{node}"]);
+    run_hg(command, "log").ok().filter(|node| !node.is_empty())
+}
+
+/// Changeset id of the working copy parent, shortened to 12 characters unless `long_id`
+pub fn current_changeset(repo_path: &Path, long_id: bool) -> Result<String> {
+    let template = if long_id { "{node}" } else { "{node|short}" };
+    let mut command = hg(repo_path)?;
+    command.args(["log", "--rev", ".", "--template", template]);
+    run_hg(command, "log")
+}
+
+/// Whether tracked files have uncommitted changes
+pub fn is_dirty(repo_path: &Path) -> Result<bool> {
+    let mut command = hg(repo_path)?;
+    command.args(["status", "--modified", "--added", "--removed", "--deleted"]);
+    Ok(!run_hg(command, "status")?.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hg_repo() -> tempfile::TempDir {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().to_str().unwrap();
+        let run = |args: &[&str]| {
+            let status = Command::new("hg")
+                .args(["--repository", path])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success(), "hg {:?} failed", args);
+        };
+        Command::new("hg").args(["init", path]).status().unwrap();
+        std::fs::write(temp.path().join("haxelib.json"), "{}").unwrap();
+        run(&["add", "haxelib.json"]);
+        run(&["commit", "--user", "test", "--message", "init"]);
+        temp
+    }
+
+    #[test]
+    #[ignore = "requires hg"]
+    fn test_current_changeset_resolves() {
+        let repo = hg_repo();
+
+        let long = current_changeset(repo.path(), true).unwrap();
+        let short = current_changeset(repo.path(), false).unwrap();
+        assert_eq!(long.len(), 40);
+        assert!(long.starts_with(&short));
+        assert_eq!(resolve(repo.path(), &short), Some(long));
+        assert_eq!(resolve(repo.path(), "doesnotexist"), None);
+    }
+
+    #[test]
+    #[ignore = "requires hg"]
+    fn test_is_dirty() {
+        let repo = hg_repo();
+
+        assert!(!is_dirty(repo.path()).unwrap());
+        std::fs::write(repo.path().join("haxelib.json"), "{\"a\":1}").unwrap();
+        assert!(is_dirty(repo.path()).unwrap());
+    }
+}
@@ -1,3 +1,11 @@
+pub mod cache;
+pub mod config;
+pub mod constraint;
 pub mod dependencies;
+pub mod graph;
+pub mod haxe_serializer;
 pub mod haxelib;
+pub mod haxelib_api;
+pub mod hg;
 pub mod json;
+pub mod proxy;
@@ -0,0 +1,67 @@
+use std::env;
+
+use anyhow::{Context, Result};
+
+/// The standard proxy variables, which reqwest, git and hg already honour on their own
+const PROXY_VARS: [&str; 4] = ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"];
+
+/// The `proxy` setting, unless one of the standard proxy variables is set, which then wins
+pub fn resolve(proxy: Option<&str>) -> Option<String> {
+    match PROXY_VARS.iter().any(|key| env::var_os(key).is_some()) {
+        true => None,
+        false => proxy.map(str::to_string),
+    }
+}
+
+/// The variables pointing a git or hg process at `proxy`, for `Command::envs`
+pub fn command_env(proxy: Option<&str>) -> Vec<(&'static str, &str)> {
+    proxy
+        .map(|proxy| vec![("http_proxy", proxy), ("https_proxy", proxy)])
+        .unwrap_or_default()
+}
+
+fn reqwest_proxy(proxy: &str) -> Result<reqwest::Proxy> {
+    reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy {}", proxy))
+}
+
+/// An http client going through `proxy`, or through the standard proxy variables without one
+pub fn client(proxy: Option<&str>) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(reqwest_proxy(proxy)?);
+    }
+    builder.build().context("Failed to create the http client")
+}
+
+/// Blocking counterpart of [`client`]
+pub fn blocking_client(proxy: Option<&str>) -> Result<reqwest::blocking::Client> {
+    let mut builder = reqwest::blocking::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(reqwest_proxy(proxy)?);
+    }
+    builder.build().context("Failed to create the http client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_env() {
+        assert!(command_env(None).is_empty());
+        assert_eq!(
+            command_env(Some("http://proxy:3128")),
+            vec![
+                ("http_proxy", "http://proxy:3128"),
+                ("https_proxy", "http://proxy:3128")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_client_rejects_invalid_proxy() {
+        assert!(client(None).is_ok());
+        assert!(client(Some("http://proxy:3128")).is_ok());
+        assert!(client(Some("not a proxy")).is_err());
+    }
+}
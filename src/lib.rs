@@ -6,9 +6,14 @@ use std::path::PathBuf;
 use anyhow::{Ok, Result};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
 use shadow_rs::shadow;
 
 use crate::commands::add_command;
+use crate::commands::install_command::GitClone;
+use crate::commands::tree_command::GraphFormat;
+use crate::commands::update_command::SemverBump;
+use crate::hmm::config::{Config, Layers, Source};
 
 shadow!(build);
 
@@ -52,8 +57,19 @@ enum Commands {
         #[arg(value_name = "HXML")]
         hxml: Option<PathBuf>,
     },
+    /// Adds the libraries an hxml file uses (`-lib name:version`, `-lib name:git:url#ref`, ...)
+    /// to hmm.json, following nested .hxml includes. Creates hmm.json if it doesn't exist
+    FromHxml {
+        /// The hxml file to read
+        #[arg(value_name = "HXML", default_value = "build.hxml")]
+        hxml: PathBuf,
+    },
     /// Checks if the dependencies are installed at their correct hmm.json versions.
     /// Optionally specify library names to check only those.
+    ///
+    /// Exit codes: 0 everything is installed, 1 something is missing, 2 something is outdated,
+    /// 3 a checkout has local changes or dependencies conflict, 4 something isn't locked.
+    /// When several apply, the highest code wins.
     #[command(visible_alias = "ch")]
     Check {
         #[command(flatten)]
@@ -65,6 +81,37 @@ enum Commands {
     Install {
         #[command(flatten)]
         filter: LibraryFilter,
+
+        /// Maximum number of dependencies to download / clone at the same time
+        /// (default: the `jobs` config setting, or 4)
+        #[arg(short, long, value_name = "N", value_parser = jobs_parser())]
+        jobs: Option<usize>,
+
+        /// Install only from the local cache, failing with the list of anything that isn't cached
+        #[arg(long)]
+        offline: bool,
+
+        /// Also install the dependencies of installed libraries that hmm.json doesn't list,
+        /// and pin them into hmm.json
+        #[arg(long)]
+        with_transitive: bool,
+    },
+    /// Downloads or clones the dependencies from hmm.json again, even ones already installed
+    /// at the right version, like `hmm reinstall -f`. Optionally specify library names to
+    /// reinstall only those. The old install is kept if that fails. Dev dependencies are left
+    /// alone, and git / hg checkouts with local changes are only replaced after asking
+    Reinstall {
+        #[command(flatten)]
+        filter: LibraryFilter,
+
+        /// Maximum number of dependencies to download / clone at the same time
+        /// (default: the `jobs` config setting, or 4)
+        #[arg(short, long, value_name = "N", value_parser = jobs_parser())]
+        jobs: Option<usize>,
+
+        /// Reinstall only from the local cache
+        #[arg(long)]
+        offline: bool,
     },
     Add(AddArgs),
     /// Installs one or more haxelibs from lib.haxe.org. Each name may be `lib` or `lib@version`.
@@ -118,6 +165,87 @@ enum Commands {
         #[command(flatten)]
         filter: LibraryFilter,
     },
+    /// Manages the user-level cache of haxelib zips and git mirrors shared by all projects.
+    /// Defaults to $XDG_CACHE_HOME/hmm-rs, override with $HMM_CACHE_DIR
+    Cache {
+        #[command(subcommand)]
+        subcommand: CacheCommands,
+    },
+    /// Reads and writes settings in the user config file ($XDG_CONFIG_HOME/hmm-rs/config.toml)
+    /// and the project's hmm.toml, next to hmm.json. Settings are layered: the user file, then
+    /// hmm.toml, then $HMM_* environment variables, then flags
+    Config {
+        #[command(subcommand)]
+        subcommand: ConfigCommands,
+    },
+    /// Imports dependencies from another package manager's files into hmm.json
+    Import {
+        #[command(subcommand)]
+        subcommand: ImportCommands,
+    },
+    /// Exports the dependencies in hmm.json for another package manager
+    Export {
+        #[command(subcommand)]
+        subcommand: ExportCommands,
+    },
+    /// Searches lib.haxe.org for haxelibs matching a query
+    Search {
+        /// Words to look for in haxelib names and descriptions
+        query: String,
+
+        /// How many results to show
+        #[arg(short, long, value_name = "N", default_value_t = 10)]
+        limit: usize,
+
+        /// Print the results as JSON, the same as `--format json`
+        #[arg(long)]
+        json: bool,
+    },
+    /// Shows a haxelib's details and every released version with its release notes,
+    /// marking the one pinned in hmm.json and the one installed
+    Info {
+        /// The haxelib to look up
+        lib: String,
+    },
+    /// Shows which haxelib and git dependencies have newer releases or commits than hmm.json pins
+    Outdated {
+        #[command(flatten)]
+        filter: LibraryFilter,
+    },
+    /// Moves the hmm.json pins of haxelib and git dependencies to their newest releases or
+    /// commits, then installs them. Optionally specify library names to update only those.
+    Update {
+        #[command(flatten)]
+        filter: LibraryFilter,
+
+        /// Only move to releases compatible with the current pin: `minor` keeps the major
+        /// version, `patch` keeps the major and minor version
+        #[arg(long, value_enum, value_name = "LEVEL")]
+        semver: Option<SemverBump>,
+
+        /// Print the new pins without changing hmm.json or installing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Ask before applying each update
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    /// Prints the dependency tree of hmm.json, following the haxelib.json of installed libraries
+    Tree {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = GraphFormat::Text)]
+        output: GraphFormat,
+    },
+    /// Shows every path from the hmm.json dependencies to a library
+    Why {
+        /// The library to explain
+        lib: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = GraphFormat::Text)]
+        output: GraphFormat,
+    },
 }
 
 #[derive(Debug, Args, Clone)]
@@ -137,9 +265,9 @@ pub struct AddArgs {
 
 #[derive(Debug, Args)]
 struct GlobalOpts {
-    /// Color
-    #[arg(long, value_enum, global = true, default_value_t = Color::Auto)]
-    color: Color,
+    /// Color (default: auto). Falls back to $HMM_COLOR, then the `color` config setting, if unset.
+    #[arg(long, value_enum, global = true)]
+    color: Option<Color>,
     /// Sets a custom hmm.json file to use
     #[arg(short, long, value_name = "JSON", default_value = "hmm.json")]
     json: Option<PathBuf>,
@@ -147,14 +275,84 @@ struct GlobalOpts {
     #[arg(long, short, global = true, action = clap::ArgAction::Count)]
     verbose: u8,
     /// Separator used in git remote names derived from URLs (default: ".").
-    /// Falls back to $HMM_REMOTE_SEPARATOR if unset.
+    /// Falls back to $HMM_REMOTE_SEPARATOR, then the `remote-separator` config setting, if unset.
     #[arg(long, global = true, value_name = "SEP")]
     remote_separator: Option<String>,
+    /// Base url of the haxelib server, used for downloads and API calls
+    /// (default: https://lib.haxe.org). Falls back to $HMM_HAXELIB_SERVER, then the
+    /// `haxelib-server` config setting, if unset.
+    #[arg(long, global = true, value_name = "URL")]
+    haxelib_server: Option<String>,
+    /// A mirror to try when the haxelib server can't be reached or answers with a 5xx.
+    /// Repeat it to add more, they're tried in order.
+    /// Falls back to $HMM_HAXELIB_MIRRORS (comma separated), then the `haxelib-mirrors` config
+    /// setting, if unset.
+    #[arg(long = "haxelib-mirror", global = true, value_name = "URL")]
+    haxelib_mirrors: Vec<String>,
+    /// The user cache of haxelib zips and git mirrors (default: $XDG_CACHE_HOME/hmm-rs).
+    /// Falls back to $HMM_CACHE_DIR, then the `cache-dir` config setting, if unset.
+    #[arg(long, global = true, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+    /// How many times a failed haxelib download is retried, waiting twice as long each time
+    /// (default: 3). Falls back to $HMM_RETRIES, then the `retries` config setting, if unset.
+    #[arg(long, global = true, value_name = "N")]
+    retries: Option<u32>,
+    /// How git dependencies are cloned (default: mirror).
+    /// Falls back to $HMM_GIT_CLONE, then the `git-clone` config setting, if unset.
+    #[arg(long, value_enum, global = true, value_name = "STRATEGY")]
+    git_clone: Option<GitClone>,
+    /// Proxy used for haxelib downloads and git, unless $HTTP_PROXY / $HTTPS_PROXY are set.
+    /// Falls back to $HMM_PROXY, then the `proxy` config setting, if unset.
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+    /// Output format of `check`, `list`, `lock check`, `search`, `info`, `outdated` and `config list`. `json` prints a single JSON document
+    /// on stdout, meant for scripts and CI
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
     //... other global options
 }
 
-#[derive(Clone, Debug, ValueEnum)]
-enum Color {
+impl GlobalOpts {
+    /// The settings given as flags, the top config layer
+    fn config(&self) -> Config {
+        let set = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+        Config {
+            haxelib_server: set(&self.haxelib_server),
+            haxelib_mirrors: (!self.haxelib_mirrors.is_empty())
+                .then(|| self.haxelib_mirrors.clone()),
+            cache_dir: self.cache_dir.clone(),
+            jobs: None,
+            retries: self.retries,
+            retry_delay_ms: None,
+            remote_separator: set(&self.remote_separator),
+            git_clone: self.git_clone,
+            color: self.color,
+            proxy: set(&self.proxy),
+        }
+    }
+
+    /// Fills the options from every config layer merged, flags included
+    fn apply(&mut self, config: &Config) {
+        self.color = config.color;
+        self.remote_separator = config.remote_separator.clone();
+        self.haxelib_server = config.haxelib_server.clone();
+        self.haxelib_mirrors = config.haxelib_mirrors.clone().unwrap_or_default();
+        self.cache_dir = config.cache_dir.clone();
+        self.retries = config.retries;
+        self.git_clone = config.git_clone;
+        self.proxy = config.proxy.clone();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
     Always,
     Auto,
     Never,
@@ -166,32 +364,200 @@ enum LockCommands {
     Check,
 }
 
+#[derive(Subcommand, Debug, Clone)]
+enum CacheCommands {
+    /// Lists the cached haxelib zips and git mirrors
+    #[command(visible_alias = "list")]
+    Ls,
+    /// Removes everything from the cache
+    Clean,
+    /// Removes cache entries that haven't been used recently
+    Prune {
+        /// Remove entries not used for this many days
+        #[arg(long, value_name = "DAYS", default_value_t = 30)]
+        days: u64,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ConfigCommands {
+    /// Prints the value of a setting, after layering
+    Get {
+        /// The setting, e.g. `jobs` or `haxelib-mirrors`
+        key: String,
+    },
+    /// Writes a setting to the project's hmm.toml, or to the user config file with --global.
+    /// `haxelib-mirrors` takes a comma separated list
+    Set {
+        /// The setting, e.g. `jobs` or `haxelib-mirrors`
+        key: String,
+        /// The value to store
+        value: String,
+
+        /// Write to the user config file instead of hmm.toml
+        #[arg(long)]
+        global: bool,
+    },
+    /// Prints every setting that's set, and where it comes from
+    #[command(visible_alias = "ls")]
+    List,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ImportCommands {
+    /// Reads the lix scope (`haxe_libraries/<lib>.hxml`), using the `# @install` line of each lib
+    Lix {
+        /// The lix scope directory
+        #[arg(value_name = "DIR", default_value = "haxe_libraries")]
+        dir: PathBuf,
+    },
+    /// Reads the `dependencies` of a library's own haxelib.json.
+    /// Dependencies without a version are pinned to their latest release
+    HaxelibJson {
+        /// The haxelib.json file, or the directory containing it
+        #[arg(value_name = "PATH", default_value = "haxelib.json")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ExportCommands {
+    /// Writes a lix scope file (`haxe_libraries/<lib>.hxml`) for every dependency.
+    /// Class paths come from the installed libraries, so run `hmm-rs install` first
+    Lix {
+        /// The lix scope directory
+        #[arg(value_name = "DIR", default_value = "haxe_libraries")]
+        dir: PathBuf,
+    },
+}
+
+/// `--jobs` takes at least 1, like the `jobs` setting
+fn jobs_parser() -> clap::builder::RangedU64ValueParser<usize> {
+    clap::builder::RangedU64ValueParser::new().range(1..)
+}
+
 pub fn run() -> Result<()> {
-    let args = Cli::parse();
+    let mut args = Cli::parse();
 
     let path = args.global_opts.json.clone().unwrap();
+    let mut layers = Layers::load(&path)?;
+    layers.push(Source::Flag, args.global_opts.config());
+    let config = layers.merged();
+    args.global_opts.apply(&config);
+    match args.global_opts.color {
+        Some(Color::Always) => {
+            console::set_colors_enabled(true);
+            console::set_colors_enabled_stderr(true);
+        }
+        Some(Color::Never) => {
+            console::set_colors_enabled(false);
+            console::set_colors_enabled_stderr(false);
+        }
+        Some(Color::Auto) | None => {}
+    }
+
     let load_deps = || hmm::json::read_json(&path);
     let remote_separator = commands::install_command::resolve_remote_separator(
         args.global_opts.remote_separator.as_deref(),
     );
+    let format = args.global_opts.format;
+    let servers = hmm::haxelib_api::HaxelibServers::resolve(
+        args.global_opts.haxelib_server.as_deref(),
+        &args.global_opts.haxelib_mirrors,
+    );
+    let proxy = hmm::proxy::resolve(config.proxy.as_deref());
+    let api = || hmm::haxelib_api::HaxelibApi::for_servers(&servers).with_proxy(proxy.as_deref());
+    // what `install` uses unless its own flags say otherwise
+    let install_options = commands::install_command::InstallOptions {
+        separator: remote_separator,
+        jobs: config
+            .jobs
+            .unwrap_or(commands::install_command::DEFAULT_JOBS),
+        offline: false,
+        with_transitive: false,
+        force: false,
+        servers: servers.clone(),
+        git_clone: args.global_opts.git_clone.unwrap_or_default(),
+        cache_dir: config
+            .cache_dir
+            .clone()
+            .unwrap_or_else(hmm::cache::cache_dir),
+        retry: commands::install_command::RetryPolicy::new(config.retries, config.retry_delay_ms),
+        proxy: proxy.clone(),
+    };
 
     match args.cmd {
         Commands::Add(add_args) => {
-            add_command::add_dependency(add_args, load_deps()?, path, &remote_separator)?
+            add_command::add_dependency(add_args, load_deps()?, path, &install_options)?
+        }
+        Commands::List { filter } => {
+            hmm::json::read_json(&path)?.print_string_list(&filter.lib, format)?
         }
-        Commands::List { filter } => hmm::json::read_json(&path)?.print_string_list(&filter.lib)?,
         Commands::Init => commands::init_command::init_hmm()?,
         Commands::Clean => commands::clean_command::remove_haxelib_folder()?,
         Commands::ToHxml { hxml } => commands::tohxml_command::dump_to_hxml(&load_deps()?, hxml)?,
-        Commands::Check { filter } => commands::check_command::check(&load_deps()?, &filter.lib)?,
-        Commands::Install { filter } => commands::install_command::install_from_hmm(
-            &load_deps()?,
-            &filter.lib,
-            &remote_separator,
-        )?,
-        Commands::Haxelib { names } => {
-            commands::haxelib_command::install_haxelibs(&names, load_deps()?, path)?
+        Commands::FromHxml { hxml } => {
+            let deps = match path.exists() {
+                true => load_deps()?,
+                false => hmm::dependencies::Dependancies {
+                    dependencies: vec![],
+                },
+            };
+            commands::fromhxml_command::import_hxml(&hxml, deps, path)?
+        }
+        Commands::Check { filter } => {
+            commands::check_command::check(&load_deps()?, &filter.lib, format)?
         }
+        Commands::Install {
+            filter,
+            jobs,
+            offline,
+            with_transitive,
+        } => {
+            let options = commands::install_command::InstallOptions {
+                jobs: jobs.unwrap_or(install_options.jobs),
+                offline,
+                with_transitive,
+                ..install_options
+            };
+            let deps = commands::install_command::resolve_constraints(
+                &api()?,
+                load_deps()?,
+                &filter.lib,
+                path.clone(),
+                offline,
+            )?;
+            commands::install_command::install_from_hmm(&deps, &filter.lib, &options)?;
+            if with_transitive {
+                commands::install_command::install_transitive_deps(load_deps()?, path, &options)?;
+            }
+        }
+        Commands::Reinstall {
+            filter,
+            jobs,
+            offline,
+        } => {
+            let options = commands::install_command::InstallOptions {
+                jobs: jobs.unwrap_or(install_options.jobs),
+                offline,
+                force: true,
+                ..install_options
+            };
+            let deps = commands::install_command::resolve_constraints(
+                &api()?,
+                load_deps()?,
+                &filter.lib,
+                path,
+                offline,
+            )?;
+            commands::install_command::install_from_hmm(&deps, &filter.lib, &options)?
+        }
+        Commands::Haxelib { names } => commands::haxelib_command::install_haxelibs(
+            &names,
+            load_deps()?,
+            path,
+            &install_options,
+        )?,
         Commands::Git {
             name,
             url,
@@ -204,7 +570,7 @@ pub fn run() -> Result<()> {
             &dir,
             load_deps()?,
             path,
-            &remote_separator,
+            &install_options,
         )?,
         Commands::Remove { filter } => {
             commands::remove_command::remove_haxelibs(load_deps()?, &filter.lib, path)?
@@ -221,14 +587,108 @@ pub fn run() -> Result<()> {
             long_id,
             filter,
         } => match subcommand {
-            Some(LockCommands::Check) => commands::lock_command::check_locked(&load_deps()?)?,
+            Some(LockCommands::Check) => {
+                commands::lock_command::check_locked(&load_deps()?, format)?
+            }
             None => commands::lock_command::lock_dependencies(
                 &load_deps()?,
                 &filter.lib,
                 args.global_opts.json.unwrap(),
                 long_id,
+                &install_options,
             )?,
         },
+        Commands::Cache { subcommand } => {
+            let cache = install_options.cache();
+            match subcommand {
+                CacheCommands::Ls => commands::cache_command::list_cache(&cache)?,
+                CacheCommands::Clean => commands::cache_command::clean_cache(&cache)?,
+                CacheCommands::Prune { days } => {
+                    commands::cache_command::prune_cache(&cache, days)?
+                }
+            }
+        }
+        Commands::Config { subcommand } => match subcommand {
+            ConfigCommands::Get { key } => commands::config_command::get(&layers, &key)?,
+            ConfigCommands::Set { key, value, global } => {
+                let file = match global {
+                    true => layers.user_path.clone().ok_or_else(|| {
+                        anyhow::anyhow!("No home directory to keep the user config file in")
+                    })?,
+                    false => layers.project_path.clone(),
+                };
+                commands::config_command::set(&file, &key, &value)?
+            }
+            ConfigCommands::List => commands::config_command::list(&layers, format)?,
+        },
+        Commands::Import { subcommand } => {
+            let deps = match path.exists() {
+                true => load_deps()?,
+                false => hmm::dependencies::Dependancies {
+                    dependencies: vec![],
+                },
+            };
+            match subcommand {
+                ImportCommands::Lix { dir } => {
+                    commands::lix_command::import_lix(&dir, deps, path)?
+                }
+                ImportCommands::HaxelibJson { path: haxelib_json } => {
+                    commands::import_command::import_haxelib_json(
+                        &api()?,
+                        &haxelib_json,
+                        deps,
+                        path,
+                    )?
+                }
+            }
+        }
+        Commands::Export { subcommand } => match subcommand {
+            ExportCommands::Lix { dir } => commands::lix_command::export_lix(&load_deps()?, &dir)?,
+        },
+        Commands::Search { query, limit, json } => {
+            let format = match json {
+                true => OutputFormat::Json,
+                false => format,
+            };
+            commands::search_command::search(&api()?, &query, limit, format)?
+        }
+        Commands::Info { lib } => {
+            let deps = path.exists().then(load_deps).transpose()?;
+            commands::info_command::info(
+                &api()?,
+                deps.as_ref(),
+                &lib,
+                format,
+            )?
+        }
+        Commands::Outdated { filter } => commands::outdated_command::outdated(
+            &api()?,
+            &load_deps()?,
+            &filter.lib,
+            format,
+            proxy.as_deref(),
+        )?,
+        Commands::Update {
+            filter,
+            semver,
+            dry_run,
+            interactive,
+        } => commands::update_command::update(
+            &api()?,
+            load_deps()?,
+            &filter.lib,
+            path,
+            &commands::update_command::UpdateOptions {
+                semver,
+                dry_run,
+                interactive,
+            },
+            &install_options,
+        )?,
+        Commands::Tree { output } => commands::tree_command::print_tree(&load_deps()?, output)?,
+        Commands::Why { lib, output } => {
+            commands::tree_command::print_why(&load_deps()?, &lib, output)?
+        }
     }
     Ok(())
 }
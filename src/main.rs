@@ -1,5 +1,15 @@
 use anyhow::Result;
 
+use hmm_rs::commands::check_command::CheckFailed;
+
 fn main() -> Result<()> {
-    hmm_rs::run()
+    if let Err(err) = hmm_rs::run() {
+        // `check` reports what's wrong through its exit code, see `hmm-rs check --help`
+        if let Some(failed) = err.downcast_ref::<CheckFailed>() {
+            eprintln!("Error: {}", failed);
+            std::process::exit(failed.code);
+        }
+        return Err(err);
+    }
+    Ok(())
 }
@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
 
 use crate::common;
@@ -7,8 +7,7 @@ use crate::common;
 fn add_git_with_multiple_names_errors() {
     let temp = common::project_with_empty_hmm_json();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args([
             "add",
@@ -26,8 +25,7 @@ fn add_git_with_multiple_names_errors() {
 fn add_no_args_errors() {
     let temp = common::project_with_empty_hmm_json();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("add")
         .assert()
@@ -0,0 +1,274 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+fn git_project(url: &str) -> assert_fs::TempDir {
+    let json = format!(
+        r#"{{
+  "dependencies": [
+    {{ "name": "mylib", "type": "git", "ref": "main", "url": "{url}" }}
+  ]
+}}"#
+    );
+    common::project_with_hmm_json(&json)
+}
+
+#[test]
+fn cache_ls_empty() {
+    let cache = assert_fs::TempDir::new().unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["cache", "ls"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("The cache is empty"));
+}
+
+#[test]
+fn git_install_goes_through_cached_mirror() {
+    let (_repo, repo_path) = common::local_git_repo_with_lib_subdir("mylib");
+    let url = common::file_url(&repo_path);
+    let cache = assert_fs::TempDir::new().unwrap();
+
+    // The first project creates the mirror...
+    let first = git_project(&url);
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(first.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Mirroring"));
+
+    // ...and the second one reuses it.
+    let second = git_project(&url);
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(second.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updating cached mirror"));
+
+    second
+        .child(".haxelib/mylib/git/mylib/haxelib.json")
+        .assert(predicate::path::is_file());
+
+    // The project clone must track the real remote, not the cache mirror.
+    let remotes = std::process::Command::new("git")
+        .args([
+            "-C",
+            second.path().join(".haxelib/mylib/git").to_str().unwrap(),
+        ])
+        .args(["remote", "-v"])
+        .output()
+        .unwrap();
+    let remotes = String::from_utf8_lossy(&remotes.stdout);
+    assert!(remotes.contains(&url), "unexpected remotes: {remotes}");
+    assert!(!remotes.contains("origin"), "unexpected remotes: {remotes}");
+
+    cargo_bin_cmd!("hmm-rs")
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["cache", "ls"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[git]"))
+        .stdout(predicate::str::contains(url.as_str()));
+}
+
+#[test]
+fn cache_prune_keeps_recent_entries() {
+    let (_repo, repo_path) = common::local_git_repo_with_lib_subdir("mylib");
+    let cache = assert_fs::TempDir::new().unwrap();
+    let project = git_project(&common::file_url(&repo_path));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(project.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("hmm-rs")
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["cache", "prune", "--days", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pruned"));
+
+    cache.child("git").assert(predicate::path::is_dir());
+    assert_eq!(
+        std::fs::read_dir(cache.child("git").path())
+            .unwrap()
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn cache_prune_rejects_days_that_overflow() {
+    let cache = assert_fs::TempDir::new().unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["cache", "prune", "--days", &u64::MAX.to_string()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is too large"));
+}
+
+#[test]
+fn cache_clean_removes_cache_dir() {
+    let cache = assert_fs::TempDir::new().unwrap();
+    cache
+        .child("haxelib/lime/8.0.0/abc.zip")
+        .write_str("zip")
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["cache", "clean"])
+        .assert()
+        .success();
+
+    cache.child("haxelib").assert(predicate::path::missing());
+}
+
+#[test]
+fn haxelib_install_uses_cached_zip_without_network() {
+    let cache = assert_fs::TempDir::new().unwrap();
+    cache
+        .child("haxelib/mylib/1.0.0/0123abcd.zip")
+        .write_binary(&common::haxelib_zip(&[
+            ("haxelib.json", r#"{"name":"mylib","version":"1.0.0"}"#),
+            ("src/Main.hx", "class Main {}"),
+        ]))
+        .unwrap();
+
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using cached"));
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+    temp.child(".haxelib/mylib/1,0,0/src/Main.hx")
+        .assert(predicate::path::is_file());
+}
+
+#[test]
+fn offline_install_lists_everything_missing_from_cache() {
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{
+  "dependencies": [
+    { "name": "lime", "type": "haxelib", "version": "8.0.0" },
+    { "name": "flixel", "type": "git", "ref": "main", "url": "https://example.invalid/flixel" }
+  ]
+}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("2 dependencies are not available"))
+        .stderr(predicate::str::contains("lime [Haxelib]: 8.0.0 is not in the cache"))
+        .stderr(predicate::str::contains(
+            "flixel [Git]: no cached mirror of https://example.invalid/flixel",
+        ));
+
+    temp.child(".haxelib/lime").assert(predicate::path::missing());
+}
+
+#[test]
+fn offline_install_keeps_listing_after_a_git_dependency_without_url() {
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{
+  "dependencies": [
+    { "name": "flixel", "type": "git", "ref": "main" },
+    { "name": "lime", "type": "haxelib", "version": "8.0.0" }
+  ]
+}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("2 dependencies are not available"))
+        .stderr(predicate::str::contains("flixel [Git]: no url in hmm.json"))
+        .stderr(predicate::str::contains(
+            "lime [Haxelib]: 8.0.0 is not in the cache",
+        ));
+}
+
+#[test]
+fn offline_install_uses_cached_zip() {
+    let cache = assert_fs::TempDir::new().unwrap();
+    cache
+        .child("haxelib/mylib/1.0.0/0123abcd.zip")
+        .write_binary(&common::haxelib_zip(&[(
+            "haxelib.json",
+            r#"{"name":"mylib","version":"1.0.0"}"#,
+        )]))
+        .unwrap();
+
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success();
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+}
+
+#[test]
+fn offline_install_clones_from_cached_mirror() {
+    let (repo, repo_path) = common::local_git_repo_with_lib_subdir("mylib");
+    let url = common::file_url(&repo_path);
+    let cache = assert_fs::TempDir::new().unwrap();
+
+    let first = git_project(&url);
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(first.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success();
+
+    // With the original remote gone, only the mirror can provide the repo.
+    drop(repo);
+
+    let second = git_project(&url);
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(second.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updating cached mirror").not());
+
+    second
+        .child(".haxelib/mylib/git/mylib/haxelib.json")
+        .assert(predicate::path::is_file());
+}
@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
 
 use crate::common;
@@ -14,8 +14,7 @@ fn check_all_haxelibs_correct() {
     let temp =
         common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0"), ("lib-b", "2.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("check")
         .assert()
@@ -34,12 +33,11 @@ fn check_detects_missing_haxelib() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("check")
         .assert()
-        .success()
+        .code(1)
         .stdout(predicate::str::contains("is not installed"));
 }
 
@@ -52,12 +50,11 @@ fn check_detects_wrong_version() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("check")
         .assert()
-        .success()
+        .code(2)
         .stdout(predicate::str::contains("is not at the correct version"));
 }
 
@@ -70,12 +67,11 @@ fn check_detects_unlocked_version() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("check")
         .assert()
-        .success()
+        .code(4)
         .stdout(predicate::str::contains("is not locked"));
 }
 
@@ -83,8 +79,7 @@ fn check_detects_unlocked_version() {
 fn check_alias_ch_works() {
     let temp = common::project_with_empty_hmm_json();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("ch")
         .assert()
@@ -103,8 +98,7 @@ fn check_filtered_only_processes_named_libs() {
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0")]);
 
     // bold ANSI codes wrap each digit; check only structural pieces around it
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["check", "lib-a"])
         .assert()
@@ -123,11 +117,170 @@ fn check_unknown_lib_warns() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["check", "nonexistent"])
         .assert()
         .success()
         .stdout(predicate::str::contains("not found in hmm.json"));
 }
+
+fn project_where_flixel_wants_lime(pinned_lime: &str) -> assert_fs::TempDir {
+    let json = format!(
+        r#"{{"dependencies": [
+            {{"name": "flixel", "type": "haxelib", "version": "5.0.0"}},
+            {{"name": "lime", "type": "haxelib", "version": "{pinned_lime}"}}
+        ]}}"#
+    );
+    let temp = common::project_with_installed_haxelibs(
+        &json,
+        &[("flixel", "5.0.0"), ("lime", pinned_lime)],
+    );
+    common::write_installed_haxelib_json(&temp, "flixel", "5.0.0", r#"{"lime": "8.0.0"}"#);
+    temp
+}
+
+#[test]
+fn check_fails_on_version_conflict() {
+    let temp = project_where_flixel_wants_lime("7.9.0");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("Version conflicts:"))
+        .stdout(predicate::str::contains("hmm.json pins 7.9.0"))
+        .stdout(predicate::str::contains("wants"))
+        .stdout(predicate::str::contains("8.0.0"))
+        .stderr(predicate::str::contains(
+            "1 version conflicts between dependencies",
+        ));
+}
+
+#[test]
+fn check_conflict_and_unlocked_dependency_exit_with_the_higher_code() {
+    let json = r#"{"dependencies": [
+        {"name": "flixel", "type": "haxelib", "version": "5.0.0"},
+        {"name": "lime", "type": "haxelib", "version": "7.9.0"},
+        {"name": "lib-a", "type": "haxelib", "version": null}
+    ]}"#;
+    let temp = common::project_with_installed_haxelibs(
+        json,
+        &[("flixel", "5.0.0"), ("lime", "7.9.0"), ("lib-a", "1.0.0")],
+    );
+    common::write_installed_haxelib_json(&temp, "flixel", "5.0.0", r#"{"lime": "8.0.0"}"#);
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["check", "--format", "json"])
+        .output()
+        .unwrap();
+    // lime conflicts (3) and lib-a isn't locked (4), the highest code wins
+    assert_eq!(output.status.code(), Some(4));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["dependencies"][1]["install_type"], "conflict");
+    assert_eq!(report["dependencies"][2]["install_type"], "not_locked");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 version conflicts"), "{stderr}");
+}
+
+#[test]
+fn check_warns_about_unreadable_haxelib_json() {
+    let temp = project_where_flixel_wants_lime("7.9.0");
+    // not valid JSON, so whatever flixel wants is unknown
+    common::write_installed_haxelib_json(&temp, "flixel", "5.0.0", "");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dependencie(s) are installed"))
+        .stdout(predicate::str::contains("Version conflicts").not())
+        .stderr(predicate::str::contains("ignoring the dependencies of"))
+        .stderr(predicate::str::contains("flixel"));
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["check", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["installed"], 2);
+    assert_eq!(report["conflicts"], serde_json::json!([]));
+}
+
+#[test]
+fn check_accepts_compatible_versions() {
+    let temp = project_where_flixel_wants_lime("8.1.0");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Version conflicts").not());
+}
+
+#[test]
+fn check_json_format() {
+    let json = r#"{
+        "dependencies": [
+            {"name": "lib-a", "type": "haxelib", "version": "1.0.0"},
+            {"name": "lib-b", "type": "haxelib", "version": "2.0.0"},
+            {"name": "lib-c", "type": "haxelib", "version": "3.0.0"}
+        ]
+    }"#;
+    let temp =
+        common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0"), ("lib-b", "1.5.0")]);
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["--format", "json", "check"])
+        .output()
+        .unwrap();
+    // lib-c is missing (1) and lib-b outdated (2), the highest code wins
+    assert_eq!(output.status.code(), Some(2));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "dependencies": [
+                {"name": "lib-a", "type": "haxelib", "install_type": "already_installed", "wants": "1.0.0", "installed": "1.0.0"},
+                {"name": "lib-b", "type": "haxelib", "install_type": "outdated", "wants": "2.0.0", "installed": "1.5.0"},
+                {"name": "lib-c", "type": "haxelib", "install_type": "missing", "wants": "3.0.0", "installed": null}
+            ],
+            "conflicts": [],
+            "installed": 1,
+            "total": 3
+        })
+    );
+}
+
+#[test]
+fn check_json_format_reports_conflicts() {
+    let temp = project_where_flixel_wants_lime("7.9.0");
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["check", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["dependencies"][1]["install_type"], "conflict");
+    assert_eq!(
+        report["conflicts"],
+        serde_json::json!([{
+            "name": "lime",
+            "version": "7.9.0",
+            "source": "hmm.json",
+            "requirements": [{"by": "flixel", "wants": "8.0.0"}]
+        }])
+    );
+}
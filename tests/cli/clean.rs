@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
@@ -7,8 +7,7 @@ fn clean_removes_haxelib_dir() {
     let temp = assert_fs::TempDir::new().unwrap();
     temp.child(".haxelib").create_dir_all().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("clean")
         .assert()
@@ -28,8 +27,7 @@ fn clean_removes_haxelib_with_contents() {
         .write_str("ref: refs/heads/main")
         .unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("clean")
         .assert()
@@ -42,8 +40,7 @@ fn clean_removes_haxelib_with_contents() {
 fn clean_fails_when_no_haxelib_dir() {
     let temp = assert_fs::TempDir::new().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("clean")
         .assert()
@@ -56,8 +53,7 @@ fn clean_alias_cl_works() {
     let temp = assert_fs::TempDir::new().unwrap();
     temp.child(".haxelib").create_dir_all().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("cl")
         .assert()
@@ -0,0 +1,304 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+#[test]
+fn config_set_writes_hmm_toml_and_get_reads_it() {
+    let home = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["config", "set", "jobs", "8"])
+        .assert()
+        .success();
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args([
+            "config",
+            "set",
+            "haxelib-mirrors",
+            "https://a.example.com,https://b.example.com",
+        ])
+        .assert()
+        .success();
+
+    temp.child("hmm.toml")
+        .assert(predicate::str::contains("jobs = 8"));
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["config", "get", "haxelib-mirrors"])
+        .assert()
+        .success()
+        .stdout("https://a.example.com,https://b.example.com\n");
+}
+
+#[test]
+fn config_set_global_writes_user_config() {
+    let home = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["config", "set", "--global", "git-clone", "blobless"])
+        .assert()
+        .success();
+
+    home.child("hmm-rs/config.toml")
+        .assert(predicate::str::contains("git-clone = \"blobless\""));
+    temp.child("hmm.toml").assert(predicate::path::missing());
+}
+
+#[test]
+fn config_set_rejects_unknown_keys_and_bad_values() {
+    let home = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["config", "set", "colour", "never"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key colour"));
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["config", "set", "git-clone", "shallow"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid value for git-clone"));
+
+    temp.child("hmm.toml").assert(predicate::path::missing());
+}
+
+#[test]
+fn config_list_reports_where_settings_come_from() {
+    let home = assert_fs::TempDir::new().unwrap();
+    home.child("hmm-rs/config.toml")
+        .write_str("jobs = 2\nproxy = \"http://proxy:3128\"\n")
+        .unwrap();
+    let temp = common::project_with_empty_hmm_json();
+    temp.child("hmm.toml").write_str("jobs = 8\n").unwrap();
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env("HMM_GIT_CLONE", "full")
+        .args([
+            "config",
+            "list",
+            "--format",
+            "json",
+            "--remote-separator",
+            "_",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "jobs": {"value": 8, "source": "project"},
+            "remote-separator": {"value": "_", "source": "flag"},
+            "git-clone": {"value": "full", "source": "env"},
+            "proxy": {"value": "http://proxy:3128", "source": "user"},
+        })
+    );
+}
+
+#[test]
+fn config_rejects_invalid_config_file() {
+    let home = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_empty_hmm_json();
+    temp.child("hmm.toml").write_str("job = 8\n").unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .arg("list")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse hmm.toml"));
+}
+
+#[test]
+fn config_rejects_env_numbers_that_do_not_fit() {
+    let home = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        // one more than u32::MAX, which used to wrap around to 0
+        .env("HMM_RETRIES", "4294967296")
+        .args(["config", "get", "retries"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "HMM_RETRIES must be a number, got 4294967296",
+        ));
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env("HMM_JOBS", "-1")
+        .args(["config", "get", "jobs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "HMM_JOBS must be a number, got -1",
+        ));
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env("HMM_JOBS", "0")
+        .args(["config", "get", "jobs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "HMM_JOBS must be at least 1, got 0",
+        ));
+}
+
+#[test]
+fn config_rejects_zero_jobs() {
+    let home = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["config", "set", "jobs", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("jobs must be at least 1"));
+    temp.child("hmm.toml").assert(predicate::path::missing());
+
+    temp.child("hmm.toml").write_str("jobs = 0\n").unwrap();
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env_remove("HMM_JOBS")
+        .arg("list")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("jobs must be at least 1"));
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["install", "--jobs", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--jobs <N>'",
+        ));
+}
+
+#[test]
+fn install_uses_haxelib_server_and_cache_dir_from_config() {
+    let zip = common::haxelib_zip(&[("haxelib.json", r#"{"name":"mylib","version":"1.0.0"}"#)]);
+    let server = common::http_server(move |path| match path {
+        "/p/mylib/1.0.0/download" => ("200 OK", zip.clone()),
+        _ => ("404 Not Found", Vec::new()),
+    });
+    let home = assert_fs::TempDir::new().unwrap();
+    let cache = assert_fs::TempDir::new().unwrap();
+    home.child("hmm-rs/config.toml")
+        .write_str(&format!("cache-dir = {:?}\n", cache.path()))
+        .unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+    temp.child("hmm.toml")
+        .write_str(&format!("haxelib-server = \"{server}\"\n"))
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env_remove("HMM_CACHE_DIR")
+        .env_remove("HMM_HAXELIB_SERVER")
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{server}/p/mylib/1.0.0/download"
+        )));
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+    cache
+        .child("haxelib/mylib/1.0.0")
+        .assert(predicate::path::is_dir());
+}
+
+#[test]
+fn install_downloads_through_the_proxy_from_config() {
+    let zip = common::haxelib_zip(&[("haxelib.json", r#"{"name":"mylib","version":"1.0.0"}"#)]);
+    // a proxy is asked for the full url, and the haxelib server itself doesn't exist
+    let proxy = common::http_server(move |path| match path {
+        "http://haxelib.invalid/p/mylib/1.0.0/download" => ("200 OK", zip.clone()),
+        _ => ("404 Not Found", Vec::new()),
+    });
+    let home = assert_fs::TempDir::new().unwrap();
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+    temp.child("hmm.toml")
+        .write_str(&format!(
+            "haxelib-server = \"http://haxelib.invalid\"\nproxy = \"{proxy}\"\n"
+        ))
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env_remove("HMM_HAXELIB_SERVER")
+        .env_remove("HMM_PROXY")
+        .env_remove("HTTP_PROXY")
+        .env_remove("HTTPS_PROXY")
+        .env_remove("http_proxy")
+        .env_remove("https_proxy")
+        .arg("install")
+        .assert()
+        .success();
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+}
+
+#[test]
+fn install_full_git_clone_skips_the_cache_mirror() {
+    let (_repo, repo_path) = common::local_git_repo_with_lib_subdir("mylib");
+    let url = common::file_url(&repo_path);
+    let home = assert_fs::TempDir::new().unwrap();
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(&format!(
+        r#"{{"dependencies": [{{ "name": "mylib", "type": "git", "ref": "main", "url": "{url}" }}]}}"#
+    ));
+    temp.child("hmm.toml")
+        .write_str("git-clone = \"full\"\n")
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Clone completed"))
+        .stdout(predicate::str::contains("Mirroring").not());
+
+    temp.child(".haxelib/mylib/git/mylib/haxelib.json")
+        .assert(predicate::path::is_file());
+    cache.child("git").assert(predicate::path::missing());
+}
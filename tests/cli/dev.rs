@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
@@ -10,8 +10,7 @@ fn dev_adds_dependency_and_creates_dev_file() {
     let source_dir = temp.child("my-lib-src");
     source_dir.create_dir_all().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["dev", "my-lib", "my-lib-src"])
         .assert()
@@ -34,8 +33,7 @@ fn dev_with_dotted_name_converts_to_commas() {
     let source_dir = temp.child("funkin-vis-src");
     source_dir.create_dir_all().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["dev", "funkin.vis", "funkin-vis-src"])
         .assert()
@@ -49,8 +47,7 @@ fn dev_with_dotted_name_converts_to_commas() {
 fn dev_fails_with_nonexistent_path() {
     let temp = common::initialized_project();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["dev", "my-lib", "/nonexistent/path/to/lib"])
         .assert()
@@ -70,8 +67,7 @@ fn dev_overwrites_existing_entry_instead_of_duplicating() {
     temp.child(".haxelib").create_dir_all().unwrap();
     temp.child("mylib-src").create_dir_all().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["dev", "mylib", "mylib-src"])
         .assert()
@@ -0,0 +1,103 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+fn read_hmm_json(temp: &assert_fs::TempDir) -> serde_json::Value {
+    serde_json::from_str(&std::fs::read_to_string(temp.child("hmm.json").path()).unwrap()).unwrap()
+}
+
+#[test]
+fn from_hxml_creates_hmm_json() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("build.hxml")
+        .write_str(
+            "# legacy build\n-cp source\n-main Main\n-lib flixel:5.2.0\n-lib foo:git:https://example.com/foo#v1.0\ncommon.hxml\n",
+        )
+        .unwrap();
+    temp.child("common.hxml").write_str("-lib lime\n").unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("from-hxml")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("flixel 5.2.0"));
+
+    assert_eq!(
+        read_hmm_json(&temp),
+        serde_json::json!({
+            "dependencies": [
+                {"name": "flixel", "type": "haxelib", "dir": null, "version": "5.2.0"},
+                {"name": "foo", "type": "git", "dir": null, "ref": "v1.0", "url": "https://example.com/foo"},
+                {"name": "lime", "type": "haxelib", "dir": null}
+            ]
+        })
+    );
+}
+
+#[test]
+fn from_hxml_merges_into_existing_hmm_json() {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [
+            {"name": "flixel", "type": "haxelib", "version": "4.0.0"},
+            {"name": "hxcpp", "type": "haxelib", "version": "4.3.2"}
+        ]}"#,
+    );
+    temp.child("game.hxml")
+        .write_str("-lib flixel:5.2.0\n")
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["from-hxml", "game.hxml"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated"));
+
+    let json = read_hmm_json(&temp);
+    let deps = json["dependencies"].as_array().unwrap();
+    assert_eq!(deps.len(), 2);
+    assert_eq!(deps[0]["version"], "5.2.0");
+    assert_eq!(deps[1]["name"], "hxcpp");
+}
+
+#[test]
+fn from_hxml_round_trips_to_hxml() {
+    let json = common::sample_fixture_content("hmm.json");
+    let temp = common::project_with_hmm_json(&json);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["to-hxml", "deps.hxml"])
+        .assert()
+        .success();
+    let before = std::fs::read_to_string(temp.child("deps.hxml").path()).unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["--json", "imported.json", "from-hxml", "deps.hxml"])
+        .assert()
+        .success();
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["--json", "imported.json", "to-hxml", "again.hxml"])
+        .assert()
+        .success();
+    let after = std::fs::read_to_string(temp.child("again.hxml").path()).unwrap();
+
+    assert_eq!(before, after);
+}
+
+#[test]
+fn from_hxml_fails_without_hxml() {
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("from-hxml")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("build.hxml"));
+}
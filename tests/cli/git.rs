@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
@@ -10,8 +10,7 @@ fn git_install_with_subdir_creates_dev_file() {
     let url = common::file_url(&repo_path);
     let temp = common::initialized_project();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["git", "mylib", &url, "main", "mylib"])
         .assert()
@@ -36,8 +35,7 @@ fn git_install_without_subdir_creates_no_dev_file() {
     let url = common::file_url(&repo_path);
     let temp = common::initialized_project();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["git", "mylib", &url, "main"])
         .assert()
@@ -64,8 +62,7 @@ fn install_from_hmm_json_with_subdir_creates_dev_file() {
     let temp = common::project_with_hmm_json(&json);
     temp.child(".haxelib").create_dir_all().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("install")
         .assert()
@@ -100,8 +97,7 @@ fn install_relinks_missing_dev_file_for_subdir_git() {
     temp.child(".haxelib").create_dir_all().unwrap();
 
     // First install creates the git clone + the `.dev` link.
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("install")
         .assert()
@@ -113,16 +109,15 @@ fn install_relinks_missing_dev_file_for_subdir_git() {
     std::fs::remove_file(dev_file.path()).unwrap();
 
     // check should flag the missing dev link.
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("check")
         .assert()
+        .code(1)
         .stdout(predicate::str::contains("missing its dev link"));
 
     // install should re-create it.
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("install")
         .assert()
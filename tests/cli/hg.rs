@@ -0,0 +1,101 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+fn hg_project(repo_path: &std::path::Path, vcs_ref: Option<&str>) -> assert_fs::TempDir {
+    let vcs_ref = vcs_ref
+        .map(|r| format!(r#", "ref": "{r}""#))
+        .unwrap_or_default();
+    let json = format!(
+        r#"{{"dependencies": [{{"name": "mylib", "type": "hg", "dir": "mylib", "url": "{}"{vcs_ref}}}]}}"#,
+        repo_path.to_str().unwrap()
+    );
+    common::project_with_hmm_json(&json)
+}
+
+fn hg_tip(repo_path: &std::path::Path) -> String {
+    let output = std::process::Command::new("hg")
+        .args(["--repository", repo_path.to_str().unwrap()])
+        .args(["log", "--rev", "tip", "--template", "{node}"])
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+#[ignore = "requires hg"]
+fn hg_install_clones_and_links_subdir() {
+    let (_repo, repo_path) = common::local_hg_repo_with_lib_subdir("mylib");
+    let temp = hg_project(&repo_path, Some(&hg_tip(&repo_path)));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("install")
+        .assert()
+        .success();
+
+    temp.child(".haxelib/mylib/hg/mylib/haxelib.json")
+        .assert(predicate::path::is_file());
+    temp.child(".haxelib/mylib/.current").assert("hg");
+    temp.child(".haxelib/mylib/.dev")
+        .assert(predicate::str::ends_with("mylib"));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 / 1"));
+}
+
+#[test]
+#[ignore = "requires hg"]
+fn hg_lock_pins_changeset() {
+    let (_repo, repo_path) = common::local_hg_repo_with_lib_subdir("mylib");
+    let temp = hg_project(&repo_path, None);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("install")
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["lock", "--long-id"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("locked to"));
+
+    let updated_json = std::fs::read_to_string(temp.child("hmm.json").path()).unwrap();
+    assert!(
+        updated_json.contains(&format!(r#""ref": "{}""#, hg_tip(&repo_path))),
+        "{updated_json}"
+    );
+}
+
+#[test]
+#[ignore = "requires hg"]
+fn hg_check_reports_local_changes() {
+    let (_repo, repo_path) = common::local_hg_repo_with_lib_subdir("mylib");
+    let temp = hg_project(&repo_path, Some(&hg_tip(&repo_path)));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("install")
+        .assert()
+        .success();
+
+    temp.child(".haxelib/mylib/hg/mylib/haxelib.json")
+        .write_str("{\"name\":\"changed\"}")
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("(local changes)"));
+}
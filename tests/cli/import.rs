@@ -0,0 +1,75 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+#[test]
+fn import_haxelib_json_pins_dependencies() {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "lime", "type": "haxelib", "version": "7.9.0"}]}"#,
+    );
+    temp.child("haxelib.json")
+        .write_str(
+            r#"{
+                "name": "mylib",
+                "version": "1.0.0",
+                "dependencies": {
+                    "lime": "8.1.0",
+                    "foo": "git:https://github.com/user/foo#v2",
+                    "bar": "hg:https://hg.example.com/bar"
+                }
+            }"#,
+        )
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["import", "haxelib-json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("lime 8.1.0"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.child("hmm.json").path()).unwrap())
+            .unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "dependencies": [
+                {"name": "bar", "type": "hg", "dir": null, "url": "https://hg.example.com/bar"},
+                {"name": "foo", "type": "git", "dir": null, "ref": "v2", "url": "https://github.com/user/foo"},
+                {"name": "lime", "type": "haxelib", "dir": null, "version": "8.1.0"}
+            ]
+        })
+    );
+}
+
+#[test]
+fn import_haxelib_json_accepts_a_directory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("lib/haxelib.json")
+        .write_str(r#"{"name": "mylib", "dependencies": {"format": "3.5.0"}}"#)
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["import", "haxelib-json", "lib"])
+        .assert()
+        .success();
+
+    temp.child("hmm.json")
+        .assert(predicate::str::contains("\"format\""));
+}
+
+#[test]
+fn import_haxelib_json_fails_without_file() {
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["import", "haxelib-json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("haxelib.json not found"));
+}
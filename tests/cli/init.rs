@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
@@ -6,8 +6,7 @@ use predicates::prelude::*;
 fn init_creates_haxelib_dir_and_hmm_json() {
     let temp = assert_fs::TempDir::new().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("init")
         .assert()
@@ -26,8 +25,7 @@ fn init_fails_when_haxelib_already_exists() {
     let temp = assert_fs::TempDir::new().unwrap();
     temp.child(".haxelib").create_dir_all().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("init")
         .assert()
@@ -42,8 +40,7 @@ fn init_does_not_overwrite_existing_hmm_json() {
     let original = r#"{"dependencies":[{"name":"test","type":"haxelib","version":"1.0.0"}]}"#;
     temp.child("hmm.json").write_str(original).unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("init")
         .assert()
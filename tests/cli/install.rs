@@ -1,4 +1,5 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
 use predicates::prelude::*;
 
 use crate::common;
@@ -15,8 +16,7 @@ fn install_does_not_panic_without_haxelib_dir() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    let assert = Command::cargo_bin("hmm-rs")
-        .unwrap()
+    let assert = cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("install")
         .assert();
@@ -38,8 +38,7 @@ fn install_git_dep_does_not_panic_without_haxelib_dir() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    let assert = Command::cargo_bin("hmm-rs")
-        .unwrap()
+    let assert = cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("install")
         .assert();
@@ -62,8 +61,7 @@ fn install_multiple_deps_does_not_panic_without_haxelib_dir() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    let assert = Command::cargo_bin("hmm-rs")
-        .unwrap()
+    let assert = cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("install")
         .assert();
@@ -83,8 +81,7 @@ fn install_selective_single_lib() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["install", "flixel"])
         .assert()
@@ -104,8 +101,7 @@ fn install_selective_multiple_libs() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["install", "flixel", "lime"])
         .assert()
@@ -124,8 +120,7 @@ fn install_unknown_lib_warns() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["install", "nonexistent"])
         .assert()
@@ -143,8 +138,7 @@ fn install_mixed_known_and_unknown_libs() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["install", "flixel", "bogus"])
         .assert()
@@ -164,8 +158,7 @@ fn install_selective_already_installed() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("flixel", "5.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["install", "flixel"])
         .assert()
@@ -177,3 +170,605 @@ fn install_selective_already_installed() {
         .stdout(predicate::str::contains("Checking lime").not())
         .stdout(predicate::str::contains("lime").not());
 }
+
+#[test]
+fn install_with_jobs_installs_all_git_deps() {
+    let (_repo_a, repo_a) = common::local_git_repo_with_lib_subdir("liba");
+    let (_repo_b, repo_b) = common::local_git_repo_with_lib_subdir("libb");
+    let json = format!(
+        r#"{{
+  "dependencies": [
+    {{ "name": "liba", "type": "git", "ref": "main", "url": "{}" }},
+    {{ "name": "libb", "type": "git", "ref": "main", "url": "{}" }}
+  ]
+}}"#,
+        common::file_url(&repo_a),
+        common::file_url(&repo_b)
+    );
+    let temp = common::project_with_hmm_json(&json);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["install", "--jobs", "2"])
+        .assert()
+        .success();
+
+    assert!(temp.path().join(".haxelib/liba/git/liba/haxelib.json").is_file());
+    assert!(temp.path().join(".haxelib/libb/git/libb/haxelib.json").is_file());
+}
+
+/// A failing dependency must not stop the others; all failures are listed at the end.
+#[test]
+fn install_reports_every_failure_at_end() {
+    let (_repo, repo_path) = common::local_git_repo_with_lib_subdir("good");
+    let missing = repo_path.parent().unwrap().join("does-not-exist");
+    let json = format!(
+        r#"{{
+  "dependencies": [
+    {{ "name": "bad-a", "type": "git", "ref": "main", "url": "{missing_url}-a" }},
+    {{ "name": "bad-b", "type": "git", "ref": "main", "url": "{missing_url}-b" }},
+    {{ "name": "good", "type": "git", "ref": "main", "url": "{good_url}" }}
+  ]
+}}"#,
+        missing_url = common::file_url(&missing),
+        good_url = common::file_url(&repo_path)
+    );
+    let temp = common::project_with_hmm_json(&json);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["install", "-j", "1"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("dependencies failed to install"))
+        .stdout(predicate::str::contains("bad-a"))
+        .stdout(predicate::str::contains("bad-b"));
+
+    assert!(temp.path().join(".haxelib/good/git/good/haxelib.json").is_file());
+}
+
+#[test]
+fn install_succeeds_when_an_installed_haxelib_json_is_malformed() {
+    let zip = common::haxelib_zip(&[("haxelib.json", r#"{"name":"mylib","version":"1.0.0",}"#)]);
+    let server = common::http_server(move |_| ("200 OK", zip.clone()));
+    let cache = assert_fs::TempDir::new().unwrap();
+
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .arg("install")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("ignoring the dependencies of"))
+        .stderr(predicate::str::contains("haxelib.json"));
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+}
+
+#[test]
+fn install_refuses_haxelib_zip_with_wrong_integrity() {
+    let zip = common::haxelib_zip(&[("haxelib.json", r#"{"name":"mylib","version":"1.0.0"}"#)]);
+    let server = common::http_server(move |_| ("200 OK", zip.clone()));
+    let cache = assert_fs::TempDir::new().unwrap();
+
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0", "integrity": "deadbeef"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .arg("install")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Integrity check failed for mylib 1.0.0"));
+
+    temp.child(".haxelib/mylib/1,0,0")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn install_downloads_again_when_cached_zip_does_not_match_integrity() {
+    use sha2::{Digest, Sha256};
+
+    let zip = mylib_zip();
+    let integrity: String = Sha256::digest(&zip)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    let server = common::http_server(move |path| match path {
+        "/p/mylib/1.0.0/download" => ("200 OK", zip.clone()),
+        _ => ("404 Not Found", Vec::new()),
+    });
+    // a newer zip of the same version, like one published again
+    let cache = common::cache_with_haxelibs(&[("mylib", "1.0.0", r#"{"other":""}"#)]);
+    let temp = common::project_with_hmm_json(&format!(
+        r#"{{"dependencies": [{{"name": "mylib", "type": "haxelib", "version": "1.0.0", "integrity": "{integrity}"}}]}}"#
+    ));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using cached").not());
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+    cache
+        .child(format!("haxelib/mylib/1.0.0/{integrity}.zip"))
+        .assert(predicate::path::is_file());
+}
+
+#[test]
+fn install_refuses_git_checkout_with_wrong_integrity() {
+    let (_repo, repo_path) = common::local_git_repo_with_lib_subdir("mylib");
+    let cache = assert_fs::TempDir::new().unwrap();
+    let json = format!(
+        r#"{{"dependencies": [{{"name": "mylib", "type": "git", "ref": "main", "url": "{}", "integrity": "deadbeef"}}]}}"#,
+        common::file_url(&repo_path)
+    );
+    let temp = common::project_with_hmm_json(&json);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Integrity check failed for mylib"));
+}
+
+#[test]
+fn install_reports_unpinned_transitive_dependencies() {
+    let cache = common::cache_with_haxelibs(&[("mylib", "1.0.0", r#"{"otherlib": "2.0.0"}"#)]);
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "transitive dependencies are not pinned in hmm.json",
+        ))
+        .stdout(predicate::str::contains(
+            "[not installed] required by mylib (2.0.0)",
+        ));
+
+    temp.child(".haxelib/otherlib").assert(predicate::path::missing());
+}
+
+#[test]
+fn install_with_transitive_installs_and_pins_dependencies() {
+    let cache = common::cache_with_haxelibs(&[
+        ("mylib", "1.0.0", r#"{"otherlib": "2.0.0"}"#),
+        ("otherlib", "2.0.0", r#"{"deeplib": "3.0.0"}"#),
+        ("deeplib", "3.0.0", "{}"),
+    ]);
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--with-transitive"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not pinned").not());
+
+    temp.child(".haxelib/otherlib/.current").assert("2.0.0");
+    temp.child(".haxelib/deeplib/.current").assert("3.0.0");
+
+    let json = std::fs::read_to_string(temp.child("hmm.json").path()).unwrap();
+    assert!(json.contains(r#""name": "otherlib""#), "{json}");
+    assert!(json.contains(r#""name": "deeplib""#), "{json}");
+}
+
+#[test]
+fn install_keeps_version_within_constraint() {
+    let cache = common::cache_with_haxelibs(&[("mylib", "1.2.0", "{}")]);
+    let hmm_json = r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.2.0", "constraint": "^1.0"}]}"#;
+    let temp = common::project_with_hmm_json(hmm_json);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Resolved").not());
+
+    temp.child(".haxelib/mylib/.current").assert("1.2.0");
+    temp.child("hmm.json").assert(hmm_json);
+}
+
+#[test]
+fn install_offline_fails_when_version_is_outside_constraint() {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.2.0", "constraint": "^2"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["install", "--offline"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no version within ^2 is pinned"));
+}
+
+#[test]
+fn install_rejects_invalid_constraint() {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "constraint": "^1 || ^2"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("install")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("mylib in hmm.json"));
+}
+
+/// A haxelib server with a single zip, at `/p/mylib/1.0.0/download`
+fn haxelib_server_with_mylib() -> String {
+    let zip = common::haxelib_zip(&[("haxelib.json", r#"{"name":"mylib","version":"1.0.0"}"#)]);
+    common::http_server(move |path| match path {
+        "/p/mylib/1.0.0/download" => ("200 OK", zip.clone()),
+        _ => ("404 Not Found", Vec::new()),
+    })
+}
+
+#[test]
+fn install_downloads_from_configured_haxelib_server() {
+    let server = haxelib_server_with_mylib();
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .arg("install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{server}/p/mylib/1.0.0/download"
+        )));
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+}
+
+#[test]
+fn install_falls_back_to_haxelib_mirror() {
+    let down = common::http_server(|_| ("503 Service Unavailable", Vec::new()));
+    let mirror = haxelib_server_with_mylib();
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--haxelib-server", &down])
+        .args(["--haxelib-mirror", &mirror])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("503"));
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+}
+
+#[test]
+fn install_fails_when_no_haxelib_server_is_up() {
+    let down = common::http_server(|_| ("502 Bad Gateway", Vec::new()));
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &down)
+        .env("HMM_HAXELIB_MIRRORS", &down)
+        .arg("install")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "no haxelib server could be reached",
+        ));
+}
+
+/// A haxelib zip of mylib 1.0.0
+fn mylib_zip() -> Vec<u8> {
+    common::haxelib_zip(&[("haxelib.json", r#"{"name":"mylib","version":"1.0.0"}"#)])
+}
+
+fn install_mylib_from(server: &str) -> (assert_fs::TempDir, assert_cmd::assert::Assert) {
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+    let assert = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", server)
+        .env("HMM_RETRY_DELAY_MS", "1")
+        .arg("install")
+        .assert();
+    (temp, assert)
+}
+
+#[test]
+fn install_resumes_dropped_download_with_range_request() {
+    let zip = mylib_zip();
+    let half = zip.len() / 2;
+    let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let server = common::raw_http_server(move |request| {
+        seen.lock().unwrap().push(request.to_string());
+        let range = request
+            .lines()
+            .find_map(|line| {
+                line.to_lowercase()
+                    .strip_prefix("range: bytes=")
+                    .map(str::to_string)
+            })
+            .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+        let mut response = match range {
+            // drop the connection halfway through the first download
+            None => format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                zip.len()
+            )
+            .into_bytes(),
+            Some(start) => format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                start,
+                zip.len() - 1,
+                zip.len(),
+                zip.len() - start
+            )
+            .into_bytes(),
+        };
+        match range {
+            None => response.extend(&zip[..half]),
+            Some(start) => response.extend(&zip[start..]),
+        }
+        response
+    });
+
+    let (temp, assert) = install_mylib_from(&server);
+    assert
+        .success()
+        .stdout(predicate::str::contains("retrying in"));
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(
+        requests[1].contains(&format!("bytes={half}-")),
+        "{}",
+        requests[1]
+    );
+}
+
+#[test]
+fn install_downloads_without_content_length() {
+    let zip = mylib_zip();
+    let server = common::raw_http_server(move |_| {
+        let mut response = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n".to_vec();
+        response.extend(&zip);
+        response
+    });
+
+    let (temp, assert) = install_mylib_from(&server);
+    assert.success();
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+    temp.child(".haxelib/mylib/1,0,0/haxelib.json")
+        .assert(predicate::path::is_file());
+}
+
+#[test]
+fn install_gives_up_after_retries() {
+    let attempts = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counted = attempts.clone();
+    let server = common::http_server(move |_| {
+        counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        ("500 Internal Server Error", Vec::new())
+    });
+    let cache = assert_fs::TempDir::new().unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .env("HMM_RETRY_DELAY_MS", "1")
+        .args(["install", "--retries", "2"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("(2/2)"))
+        .stdout(predicate::str::contains(
+            "no haxelib server could be reached",
+        ));
+
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+}
+
+#[test]
+fn install_replaces_half_extracted_version() {
+    let cache = common::cache_with_haxelibs(&[("mylib", "1.0.0", "{}")]);
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+    // what a crash halfway through extracting used to leave behind, minus `.current`
+    temp.child(".haxelib/mylib/1,0,0/leftover.hx")
+        .write_str("class Leftover {}")
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success();
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+    temp.child(".haxelib/mylib/1,0,0/haxelib.json")
+        .assert(predicate::path::is_file());
+    temp.child(".haxelib/mylib/1,0,0/leftover.hx")
+        .assert(predicate::path::missing());
+    let entries: Vec<_> = std::fs::read_dir(temp.path().join(".haxelib/mylib"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(entries.len(), 2, "{entries:?}");
+}
+
+#[test]
+fn install_with_corrupt_zip_leaves_nothing_behind() {
+    let server = common::http_server(|_| ("200 OK", b"not a zip".to_vec()));
+    let (temp, assert) = install_mylib_from(&server);
+    assert
+        .failure()
+        .stdout(predicate::str::contains("may be corrupted"));
+
+    temp.child(".haxelib/mylib/.current")
+        .assert(predicate::path::missing());
+    let entries: Vec<_> = std::fs::read_dir(temp.path().join(".haxelib/mylib"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert!(entries.is_empty(), "{entries:?}");
+}
+
+#[cfg(unix)]
+#[test]
+fn install_keeps_modes_symlinks_and_mtimes_from_the_zip() {
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+
+    let modified = zip::DateTime::from_date_and_time(2020, 2, 29, 12, 30, 10).unwrap();
+    let options = zip::write::SimpleFileOptions::default().last_modified_time(modified);
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    writer.start_file("haxelib.json", options).unwrap();
+    writer
+        .write_all(br#"{"name":"mylib","version":"1.0.0"}"#)
+        .unwrap();
+    writer
+        .start_file("bin/run.sh", options.unix_permissions(0o755))
+        .unwrap();
+    writer.write_all(b"#!/bin/sh\n").unwrap();
+    writer.add_symlink("run", "bin/run.sh", options).unwrap();
+    writer
+        .add_symlink("bin/json", "../haxelib.json", options)
+        .unwrap();
+    writer
+        .add_symlink("passwd", "../../../etc/passwd", options)
+        .unwrap();
+    writer.add_symlink("root", "/", options).unwrap();
+    let zip = writer.finish().unwrap().into_inner();
+
+    let cache = assert_fs::TempDir::new().unwrap();
+    cache
+        .child("haxelib/mylib/1.0.0/0123abcd.zip")
+        .write_binary(&zip)
+        .unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "skipping symlink passwd -> ../../../etc/passwd",
+        ));
+
+    let lib = temp.path().join(".haxelib/mylib/1,0,0");
+    let script = std::fs::metadata(lib.join("bin/run.sh")).unwrap();
+    assert_eq!(script.permissions().mode() & 0o777, 0o755);
+    // 2020-02-29T12:30:10Z
+    let mtime = script
+        .modified()
+        .unwrap()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap();
+    assert_eq!(mtime.as_secs(), 1582979410);
+
+    assert_eq!(
+        std::fs::read_link(lib.join("run")).unwrap(),
+        std::path::Path::new("bin/run.sh")
+    );
+    assert_eq!(
+        std::fs::read_to_string(lib.join("bin/json")).unwrap(),
+        r#"{"name":"mylib","version":"1.0.0"}"#
+    );
+    assert!(lib.join("passwd").symlink_metadata().is_err());
+    assert!(lib.join("root").symlink_metadata().is_err());
+}
+
+#[test]
+fn install_prefers_the_extended_timestamp_from_the_zip() {
+    use std::io::Write;
+
+    // the DOS time is the packer's local time, the extended timestamp is UTC and an hour later
+    let modified = zip::DateTime::from_date_and_time(2020, 2, 29, 12, 30, 10).unwrap();
+    let mut options = zip::write::FullFileOptions::default().last_modified_time(modified);
+    let mut extended = vec![1];
+    extended.extend_from_slice(&(1582979410u32 + 3600).to_le_bytes());
+    options.add_extra_data(0x5455, extended, false).unwrap();
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    writer.start_file("haxelib.json", options).unwrap();
+    writer
+        .write_all(br#"{"name":"mylib","version":"1.0.0"}"#)
+        .unwrap();
+    let zip = writer.finish().unwrap().into_inner();
+
+    let cache = assert_fs::TempDir::new().unwrap();
+    cache
+        .child("haxelib/mylib/1.0.0/0123abcd.zip")
+        .write_binary(&zip)
+        .unwrap();
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success();
+
+    let mtime = std::fs::metadata(temp.path().join(".haxelib/mylib/1,0,0/haxelib.json"))
+        .unwrap()
+        .modified()
+        .unwrap()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap();
+    assert_eq!(mtime.as_secs(), 1582979410 + 3600);
+}
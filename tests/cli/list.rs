@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
 
 use crate::common;
@@ -8,8 +8,7 @@ fn list_shows_all_dependencies() {
     let json = common::sample_fixture_content("hmm.json");
     let temp = common::project_with_hmm_json(&json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("list")
         .assert()
@@ -27,8 +26,7 @@ fn list_alias_ls_works() {
     let json = common::sample_fixture_content("hmm.json");
     let temp = common::project_with_hmm_json(&json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("ls")
         .assert()
@@ -41,8 +39,7 @@ fn list_specific_library() {
     let json = common::sample_fixture_content("hmm.json");
     let temp = common::project_with_hmm_json(&json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["list", "flixel-addons"])
         .assert()
@@ -54,8 +51,7 @@ fn list_specific_library() {
 fn list_empty_deps() {
     let temp = common::project_with_empty_hmm_json();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("list")
         .assert()
@@ -66,10 +62,46 @@ fn list_empty_deps() {
 fn list_fails_without_hmm_json() {
     let temp = assert_fs::TempDir::new().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("list")
         .assert()
         .failure();
 }
+
+#[test]
+fn list_json_format() {
+    let json = common::sample_fixture_content("hmm.json");
+    let temp = common::project_with_hmm_json(&json);
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["list", "flixel-addons", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        list,
+        serde_json::json!({
+            "dependencies": [
+                {"name": "flixel-addons", "type": "haxelib", "dir": null, "version": "3.3.0"}
+            ]
+        })
+    );
+}
+
+#[test]
+fn list_shows_constraint() {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "lime", "type": "haxelib", "version": "8.1.0", "constraint": ">=8.0.0 <9"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("constraint: >=8.0.0 <9"));
+}
@@ -0,0 +1,114 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+#[test]
+fn import_lix_reads_scope_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("haxe_libraries/flixel.hxml")
+        .write_str(
+            "-lib lime\n# @install: lix --silent download \"haxelib:/flixel#5.6.1\" into flixel/5.6.1/haxelib\n-cp ${HAXE_LIBCACHE}/flixel/5.6.1/haxelib/\n-D flixel=5.6.1\n",
+        )
+        .unwrap();
+    temp.child("haxe_libraries/hxcpp.hxml")
+        .write_str(
+            "# @install: lix --silent download \"gh://github.com/HaxeFoundation/hxcpp#904ea40\" into hxcpp/4.3.2/github/904ea40\n-cp ${HAXE_LIBCACHE}/hxcpp/4.3.2/github/904ea40/\n-D hxcpp=4.3.2\n",
+        )
+        .unwrap();
+    temp.child("haxe_libraries/archive.hxml")
+        .write_str("# @install: lix --silent download \"https://example.com/archive.zip\" into archive/1.0.0/http\n")
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["import", "lix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unsupported lix source"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.child("hmm.json").path()).unwrap())
+            .unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "dependencies": [
+                {"name": "flixel", "type": "haxelib", "dir": null, "version": "5.6.1"},
+                {"name": "hxcpp", "type": "git", "dir": null, "ref": "904ea40", "url": "https://github.com/HaxeFoundation/hxcpp"}
+            ]
+        })
+    );
+}
+
+#[test]
+fn import_lix_fails_without_scope() {
+    let temp = common::project_with_empty_hmm_json();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["import", "lix"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("haxe_libraries"));
+}
+
+#[test]
+fn export_lix_uses_installed_class_path() {
+    let json = r#"{"dependencies": [
+        {"name": "lime", "type": "haxelib", "version": "8.1.1"},
+        {"name": "flixel", "type": "git", "url": "https://github.com/HaxeFlixel/flixel", "ref": "abc123"},
+        {"name": "unlocked", "type": "haxelib"}
+    ]}"#;
+    let temp = common::project_with_installed_haxelibs(json, &[("lime", "8.1.1")]);
+    temp.child(".haxelib/lime/8,1,1/haxelib.json")
+        .write_str(r#"{"name": "lime", "version": "8.1.1", "classPath": "src", "dependencies": {"format": ""}}"#)
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["export", "lix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not locked to a version"));
+
+    temp.child("haxe_libraries/lime.hxml").assert(
+        "-lib format\n# @install: lix --silent download \"haxelib:/lime#8.1.1\" into lime/8.1.1/haxelib\n-cp ${HAXE_LIBCACHE}/lime/8.1.1/haxelib/src/\n-D lime=8.1.1\n",
+    );
+    temp.child("haxe_libraries/flixel.hxml")
+        .assert(predicate::str::contains(
+            "download \"gh://github.com/HaxeFlixel/flixel#abc123\" into flixel/0.0.0/github/abc123",
+        ));
+    temp.child("haxe_libraries/unlocked.hxml")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn export_then_import_lix_round_trips() {
+    let json = common::sample_fixture_content("hmm.json");
+    let temp = common::project_with_hmm_json(&json);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["export", "lix"])
+        .assert()
+        .success();
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["--json", "imported.json", "import", "lix"])
+        .assert()
+        .success();
+
+    let read = |name: &str| -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(temp.child(name).path()).unwrap()).unwrap()
+    };
+    let mut original = read("hmm.json");
+    let mut imported = read("imported.json");
+    for json in [&mut original, &mut imported] {
+        for dep in json["dependencies"].as_array_mut().unwrap() {
+            dep.as_object_mut().unwrap().remove("dir");
+        }
+    }
+    assert_eq!(original, imported);
+}
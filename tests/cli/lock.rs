@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
@@ -13,8 +13,7 @@ fn lock_haxelib_writes_version_to_json() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "3.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("lock")
         .assert()
@@ -34,8 +33,7 @@ fn lock_already_locked_is_skipped() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("lock")
         .assert()
@@ -52,8 +50,7 @@ fn lock_check_detects_unlocked() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["lock", "check"])
         .assert()
@@ -70,8 +67,7 @@ fn lock_check_passes_when_all_locked() {
     }"#;
     let temp = common::project_with_hmm_json(json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["lock", "check"])
         .assert()
@@ -92,8 +88,7 @@ fn lock_selective_locks_only_named_libs() {
         &[("lib-a", "3.0.0"), ("lib-b", "4.0.0")],
     );
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["lock", "lib-a"])
         .assert()
@@ -113,8 +108,7 @@ fn lock_unknown_lib_warns_and_proceeds() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "3.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["lock", "lib-a", "nonexistent"])
         .assert()
@@ -122,3 +116,121 @@ fn lock_unknown_lib_warns_and_proceeds() {
         .stdout(predicate::str::contains("not found in hmm.json"))
         .stdout(predicate::str::contains("locked to"));
 }
+
+#[test]
+fn lock_haxelib_records_zip_integrity() {
+    use sha2::{Digest, Sha256};
+
+    let zip = common::haxelib_zip(&[("haxelib.json", r#"{"name":"lib-a"}"#)]);
+    let cache = assert_fs::TempDir::new().unwrap();
+    cache
+        .child("haxelib/lib-a/3.0.0/0123abcd.zip")
+        .write_binary(&zip)
+        .unwrap();
+
+    let json = r#"{
+        "dependencies": [
+            {"name": "lib-a", "type": "haxelib", "version": null}
+        ]
+    }"#;
+    let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "3.0.0")]);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("lock")
+        .assert()
+        .success();
+
+    let expected: String = Sha256::digest(&zip)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    let updated_json = std::fs::read_to_string(temp.child("hmm.json").path()).unwrap();
+    assert!(
+        updated_json.contains(&format!(r#""integrity": "{expected}""#)),
+        "{updated_json}"
+    );
+}
+
+#[test]
+fn lock_git_records_tree_hash() {
+    let (_repo, repo_path) = common::local_git_repo_with_lib_subdir("mylib");
+    let cache = assert_fs::TempDir::new().unwrap();
+    let json = format!(
+        r#"{{"dependencies": [{{"name": "mylib", "type": "git", "ref": "main", "url": "{}"}}]}}"#,
+        common::file_url(&repo_path)
+    );
+    let temp = common::project_with_hmm_json(&json);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("lock")
+        .assert()
+        .success();
+
+    let tree = std::process::Command::new("git")
+        .args([
+            "-C",
+            repo_path.to_str().unwrap(),
+            "rev-parse",
+            "HEAD^{tree}",
+        ])
+        .output()
+        .unwrap();
+    let tree = String::from_utf8_lossy(&tree.stdout).trim().to_string();
+    let updated_json = std::fs::read_to_string(temp.child("hmm.json").path()).unwrap();
+    assert!(
+        updated_json.contains(&format!(r#""integrity": "{tree}""#)),
+        "{updated_json}"
+    );
+
+    // The recorded tree hash is verified by a fresh install
+    std::fs::remove_dir_all(temp.child(".haxelib").path()).unwrap();
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .arg("install")
+        .assert()
+        .success();
+}
+
+#[test]
+fn lock_check_json_format() {
+    let json = r#"{
+        "dependencies": [
+            {"name": "lib-a", "type": "haxelib", "version": "1.0.0"},
+            {"name": "lib-b", "type": "haxelib", "version": null},
+            {"name": "lib-c", "type": "dev", "path": "../lib-c"}
+        ]
+    }"#;
+    let temp = common::project_with_hmm_json(json);
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["lock", "check", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "dependencies": [
+                {"name": "lib-a", "type": "haxelib", "status": "locked", "reason": null},
+                {"name": "lib-b", "type": "haxelib", "status": "not_locked", "reason": "no version specified"},
+                {"name": "lib-c", "type": "dev", "status": "not_applicable", "reason": null}
+            ],
+            "locked": 2,
+            "total": 3
+        })
+    );
+}
@@ -1,12 +1,23 @@
 mod add;
+mod cache;
 mod check;
 mod clean;
+mod config;
 mod dev;
+mod from_hxml;
 mod git;
+mod hg;
+mod import;
 mod init;
 mod install;
+mod lix;
 mod list;
 mod lock;
+mod outdated;
+mod reinstall;
 mod remove;
+mod search;
 mod to_hxml;
+mod tree;
+mod update;
 mod upgrade;
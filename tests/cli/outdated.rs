@@ -0,0 +1,82 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+
+use crate::common;
+
+fn outdated_json(temp: &assert_fs::TempDir) -> serde_json::Value {
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["outdated", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn outdated_git_commit_behind_head() {
+    let (_source, repo, first, second) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), &first);
+
+    assert_eq!(
+        outdated_json(&temp),
+        serde_json::json!({
+            "dependencies": [{
+                "name": "mylib",
+                "type": "git",
+                "current": null,
+                "wanted": first,
+                "latest": &second[..7],
+                "outdated": true
+            }],
+            "outdated": 1,
+            "total": 1
+        })
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("outdated")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Latest"))
+        .stdout(predicate::str::contains(&second[..7]))
+        .stdout(predicate::str::contains("1 / 1 dependencies are outdated"));
+}
+
+#[test]
+fn outdated_git_tag_behind_newest_tag() {
+    let (_source, repo, _, _) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), "v1.0.0");
+
+    let report = outdated_json(&temp);
+    assert_eq!(report["dependencies"][0]["latest"], "v1.1.0");
+    assert_eq!(report["dependencies"][0]["outdated"], true);
+}
+
+#[test]
+fn outdated_git_up_to_date() {
+    let (_source, repo, _, second) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), &second);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("outdated")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All dependencies are up to date"));
+}
+
+#[test]
+fn outdated_skips_dev_dependencies() {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "local", "type": "dev", "path": "../local"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("outdated")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No haxelib or git dependencies"));
+}
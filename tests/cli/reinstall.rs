@@ -0,0 +1,169 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+#[test]
+fn reinstall_replaces_installed_haxelib() {
+    let cache = common::cache_with_haxelibs(&[("mylib", "1.0.0", "{}")]);
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success();
+    temp.child(".haxelib/mylib/1,0,0/stray.txt")
+        .write_str("left over")
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["reinstall", "--offline"])
+        .assert()
+        .success();
+
+    temp.child(".haxelib/mylib/1,0,0/stray.txt")
+        .assert(predicate::path::missing());
+    temp.child(".haxelib/mylib/1,0,0/haxelib.json")
+        .assert(predicate::path::is_file());
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+}
+
+fn installed_haxelib_project(cache: &assert_fs::TempDir) -> assert_fs::TempDir {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "mylib", "type": "haxelib", "version": "1.0.0"}]}"#,
+    );
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .args(["install", "--offline"])
+        .assert()
+        .success();
+    temp
+}
+
+#[test]
+fn reinstall_downloads_haxelib_again() {
+    let cache = common::cache_with_haxelibs(&[("mylib", "1.0.0", "{}")]);
+    let temp = installed_haxelib_project(&cache);
+    let zip = common::haxelib_zip(&[
+        ("haxelib.json", r#"{"name":"mylib","version":"1.0.0"}"#),
+        ("fresh.txt", "from the server"),
+    ]);
+    let server = common::http_server(move |_| ("200 OK", zip.clone()));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .arg("reinstall")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using cached").not());
+
+    temp.child(".haxelib/mylib/1,0,0/fresh.txt")
+        .assert("from the server");
+}
+
+#[test]
+fn reinstall_keeps_old_install_when_download_fails() {
+    let cache = common::cache_with_haxelibs(&[("mylib", "1.0.0", "{}")]);
+    let temp = installed_haxelib_project(&cache);
+    let server = common::http_server(|_| ("404 Not Found", Vec::new()));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_CACHE_DIR", cache.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .arg("reinstall")
+        .assert()
+        .failure();
+
+    temp.child(".haxelib/mylib/.current").assert("1.0.0");
+    temp.child(".haxelib/mylib/1,0,0/haxelib.json")
+        .assert(predicate::path::is_file());
+    let entries: Vec<_> = std::fs::read_dir(temp.path().join(".haxelib"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(entries, ["mylib"]);
+}
+
+fn installed_git_project() -> (assert_fs::TempDir, assert_fs::TempDir) {
+    let (source, repo_path) = common::local_git_repo_with_lib_subdir("");
+    let temp = common::initialized_project();
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["git", "mylib", &common::file_url(&repo_path), "main"])
+        .assert()
+        .success();
+    (source, temp)
+}
+
+#[test]
+fn reinstall_reclones_clean_git_checkout() {
+    let (_source, temp) = installed_git_project();
+    temp.child(".haxelib/mylib/marker").write_str("").unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("reinstall")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cloning mylib"));
+
+    temp.child(".haxelib/mylib/marker")
+        .assert(predicate::path::missing());
+    temp.child(".haxelib/mylib/git/README.md").assert("root\n");
+}
+
+#[test]
+fn reinstall_keeps_dirty_git_checkout_unless_confirmed() {
+    let (_source, temp) = installed_git_project();
+    let readme = temp.child(".haxelib/mylib/git/README.md");
+    readme.write_str("local work\n").unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("reinstall")
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("has uncommitted changes"))
+        .stdout(predicate::str::contains("Skipping"));
+    readme.assert("local work\n");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("reinstall")
+        .write_stdin("y\n")
+        .assert()
+        .success();
+    readme.assert("root\n");
+}
+
+#[test]
+fn reinstall_leaves_dev_dependencies_alone() {
+    let temp = common::initialized_project();
+    temp.child("my-lib-src").create_dir_all().unwrap();
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["dev", "my-lib", "my-lib-src"])
+        .assert()
+        .success();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("reinstall")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is a dev dependency, skipping"));
+
+    temp.child(".haxelib/my-lib/.dev")
+        .assert(predicate::path::is_file());
+}
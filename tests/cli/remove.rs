@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
@@ -8,8 +8,7 @@ use crate::common;
 fn remove_no_args_errors() {
     let temp = common::project_with_empty_hmm_json();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("remove")
         .assert()
@@ -26,8 +25,7 @@ fn remove_drops_entry_from_hmm_json() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0"), ("lib-b", "2.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["remove", "lib-a"])
         .assert()
@@ -51,8 +49,7 @@ fn remove_deletes_haxelib_folder() {
     let lib_dir = temp.child(".haxelib/lib-a");
     assert!(lib_dir.path().exists());
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["remove", "lib-a"])
         .assert()
@@ -75,8 +72,7 @@ fn remove_multiple_libs() {
         &[("lib-a", "1.0.0"), ("lib-b", "2.0.0"), ("lib-c", "3.0.0")],
     );
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["remove", "lib-a", "lib-c"])
         .assert()
@@ -97,8 +93,7 @@ fn remove_unknown_warns_and_proceeds() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["remove", "lib-a", "nonexistent"])
         .assert()
@@ -118,8 +113,7 @@ fn remove_alias_rm_works() {
     }"#;
     let temp = common::project_with_installed_haxelibs(json, &[("lib-a", "1.0.0")]);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["rm", "lib-a"])
         .assert()
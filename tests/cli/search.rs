@@ -0,0 +1,39 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+
+use crate::common;
+
+/// A lib.haxe.org stand-in that finds `flixel` for any query
+fn haxelib_server() -> String {
+    common::http_server(|path| {
+        let body = match path.contains("search") {
+            true => "hxrloy2:idi1y4:namey6:flixelgh",
+            false => "hxroy4:namey6:flixely4:descy15:A%20descriptiony10:curversiony5:6.0.0y9:downloadsi42g",
+        };
+        ("200 OK", body.as_bytes().to_vec())
+    })
+}
+
+#[test]
+fn search_json_flag_prints_json() {
+    let server = haxelib_server();
+    let temp = common::project_with_empty_hmm_json();
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .env("HMM_HAXELIB_SERVER", &server)
+        .args(["search", "flixel", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "results": [
+                {"name": "flixel", "version": "6.0.0", "description": "A description", "downloads": 42}
+            ],
+            "total": 1
+        })
+    );
+}
@@ -1,4 +1,4 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use assert_fs::prelude::*;
 use predicates::prelude::*;
 
@@ -9,8 +9,7 @@ fn to_hxml_outputs_libs_to_stdout() {
     let json = common::sample_fixture_content("hmm.json");
     let temp = common::project_with_hmm_json(&json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("to-hxml")
         .assert()
@@ -34,8 +33,7 @@ fn to_hxml_writes_to_file() {
     let json = common::sample_fixture_content("hmm.json");
     let temp = common::project_with_hmm_json(&json);
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["to-hxml", "output.hxml"])
         .assert()
@@ -53,8 +51,7 @@ fn to_hxml_with_json_flag() {
     let temp = assert_fs::TempDir::new().unwrap();
     temp.child("custom.json").write_str(&json).unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .args(["--json", "custom.json", "to-hxml"])
         .assert()
@@ -68,11 +65,26 @@ fn to_hxml_with_json_flag() {
 fn to_hxml_fails_when_no_hmm_json() {
     let temp = assert_fs::TempDir::new().unwrap();
 
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .current_dir(temp.path())
         .arg("to-hxml")
         .assert()
         .failure()
         .stderr(predicate::str::contains("not found"));
 }
+
+#[test]
+fn to_hxml_outputs_hg_libs() {
+    let temp = common::project_with_hmm_json(
+        r#"{"dependencies": [{"name": "hglib", "type": "hg", "ref": "default", "url": "https://hg.example.com/hglib"}]}"#,
+    );
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("to-hxml")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "-lib hglib:hg:https://hg.example.com/hglib#default",
+        ));
+}
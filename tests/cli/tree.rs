@@ -0,0 +1,165 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+use crate::common;
+
+/// flixel -> { lime (any), openfl 9.0.0 -> lime 8.0.0 }, with flixel and lime in hmm.json
+fn project_with_dependency_graph() -> assert_fs::TempDir {
+    let temp = common::project_with_installed_haxelibs(
+        r#"{"dependencies": [
+            {"name": "flixel", "type": "haxelib", "version": "5.0.0"},
+            {"name": "lime", "type": "haxelib", "version": "8.0.0"}
+        ]}"#,
+        &[("flixel", "5.0.0"), ("openfl", "9.0.0"), ("lime", "8.0.0")],
+    );
+    common::write_installed_haxelib_json(
+        &temp,
+        "flixel",
+        "5.0.0",
+        r#"{"lime": "", "openfl": "9.0.0"}"#,
+    );
+    common::write_installed_haxelib_json(&temp, "openfl", "9.0.0", r#"{"lime": "8.0.0"}"#);
+    common::write_installed_haxelib_json(&temp, "lime", "8.0.0", "{}");
+    temp
+}
+
+#[test]
+fn tree_prints_transitive_dependencies() {
+    let temp = project_with_dependency_graph();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("flixel 5.0.0"))
+        .stdout(predicate::str::contains("├── lime 8.0.0"))
+        .stdout(predicate::str::contains("└── openfl 9.0.0"))
+        .stdout(predicate::str::contains("    └── lime 8.0.0"));
+}
+
+#[test]
+fn tree_outputs_json() {
+    let temp = project_with_dependency_graph();
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["tree", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let tree: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(tree[0]["name"], "flixel");
+    assert_eq!(tree[0]["pinned"], true);
+    let openfl = &tree[0]["dependencies"][1];
+    assert_eq!(openfl["name"], "openfl");
+    assert_eq!(openfl["pinned"], false);
+    assert_eq!(openfl["wants"], "9.0.0");
+    assert_eq!(openfl["dependencies"][0]["name"], "lime");
+}
+
+#[test]
+fn tree_json_marks_repeated_libraries() {
+    let temp = project_with_dependency_graph();
+    // lime now has dependencies of its own, and openfl reaches it a second time
+    common::write_installed_haxelib_json(&temp, "lime", "8.0.0", r#"{"hxcpp": ""}"#);
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["tree", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let tree: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let lime = &tree[0]["dependencies"][0];
+    assert_eq!(lime["name"], "lime");
+    assert_eq!(lime["dependencies"][0]["name"], "hxcpp");
+    let repeated = &tree[0]["dependencies"][1]["dependencies"][0];
+    assert_eq!(repeated["name"], "lime");
+    assert_eq!(repeated["wants"], "8.0.0");
+    assert_eq!(repeated["repeated"], true);
+    assert!(repeated.get("dependencies").is_none());
+    // the hmm.json dependencies themselves are always expanded
+    assert_eq!(tree[1]["dependencies"][0]["name"], "hxcpp");
+}
+
+#[test]
+fn tree_dot_escapes_quotes_and_backslashes() {
+    let temp = project_with_dependency_graph();
+    temp.child(".haxelib/lime/8,0,0/haxelib.json")
+        .write_str(r#"{"name": "lime", "version": "8.0\"\\beta", "dependencies": {}}"#)
+        .unwrap();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["tree", "-o", "dot"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""lime" [label="lime 8.0\"\\beta"];"#,
+        ));
+}
+
+#[test]
+fn tree_outputs_dot() {
+    let temp = project_with_dependency_graph();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["tree", "-o", "dot"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph dependencies {"))
+        .stdout(predicate::str::contains(r#""hmm.json" -> "flixel";"#))
+        .stdout(predicate::str::contains(
+            r#""openfl" -> "lime" [label="8.0.0"];"#,
+        ));
+}
+
+#[test]
+fn why_lists_every_path() {
+    let temp = project_with_dependency_graph();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["why", "lime"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is required by:"))
+        .stdout(predicate::str::contains("hmm.json"))
+        .stdout(predicate::str::contains("-> lime (any version)"))
+        .stdout(predicate::str::contains(
+            "-> openfl (9.0.0) -> lime (8.0.0)",
+        ));
+}
+
+#[test]
+fn why_outputs_json_paths() {
+    let temp = project_with_dependency_graph();
+
+    let output = cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["why", "openfl", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let why: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(why["pinned"], false);
+    assert_eq!(why["paths"], serde_json::json!([["flixel", "openfl"]]));
+}
+
+#[test]
+fn why_unknown_lib_fails() {
+    let temp = project_with_dependency_graph();
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["why", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("nope is not a dependency"));
+}
@@ -0,0 +1,197 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+
+use crate::common;
+
+fn pinned_ref(temp: &assert_fs::TempDir) -> String {
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.path().join("hmm.json")).unwrap())
+            .unwrap();
+    json["dependencies"][0]["ref"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn update_git_commit_moves_to_branch_tip_and_installs() {
+    let (_source, repo, first, second) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), &first);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("update")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updating"));
+
+    assert_eq!(pinned_ref(&temp), second);
+    assert_eq!(
+        common::git_head(&temp.path().join(".haxelib/mylib/git")),
+        second
+    );
+}
+
+#[test]
+fn update_git_tag_moves_to_newest_tag() {
+    let (_source, repo, _, _) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), "v1.0.0");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("update")
+        .assert()
+        .success();
+
+    assert_eq!(pinned_ref(&temp), "v1.1.0");
+}
+
+#[test]
+fn update_semver_patch_keeps_minor_version() {
+    let (_source, repo, _, _) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), "v1.0.0");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["update", "--semver", "patch"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All dependencies are up to date"));
+
+    assert_eq!(pinned_ref(&temp), "v1.0.0");
+}
+
+#[test]
+fn update_dry_run_leaves_hmm_json_alone() {
+    let (_source, repo, first, second) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), &first);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["update", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would update"))
+        .stdout(predicate::str::contains(second));
+
+    assert_eq!(pinned_ref(&temp), first);
+    assert!(!temp.path().join(".haxelib/mylib").exists());
+}
+
+#[test]
+fn update_interactive_skips_declined() {
+    let (_source, repo, first, _) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), &first);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["update", "--interactive"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing selected"));
+
+    assert_eq!(pinned_ref(&temp), first);
+}
+
+#[test]
+fn update_only_named_libs() {
+    let (_source, repo, first, _) = common::local_git_repo_with_two_releases();
+    let temp = common::project_pinning(&common::file_url(&repo), &first);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .args(["update", "otherlib"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All dependencies are up to date"));
+
+    assert_eq!(pinned_ref(&temp), first);
+}
+
+#[test]
+fn update_git_branch_keeps_following_the_branch() {
+    let (_source, repo, first, _) = common::local_git_repo_with_two_releases();
+    common::run_git(&repo, &["checkout", "-qb", "dev", &first]);
+    std::fs::write(repo.join("dev.txt"), "one\n").unwrap();
+    common::run_git(&repo, &["add", "dev.txt"]);
+    common::run_git(&repo, &["commit", "-qm", "dev one"]);
+    let temp = common::project_pinning(&common::file_url(&repo), "dev");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("install")
+        .assert()
+        .success();
+
+    std::fs::write(repo.join("dev.txt"), "two\n").unwrap();
+    common::run_git(&repo, &["commit", "-qam", "dev two"]);
+    let tip = common::git_head(&repo);
+    // the default branch is where a lost branch ref would end up
+    common::run_git(&repo, &["checkout", "-q", "main"]);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("update")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&tip));
+
+    assert_eq!(pinned_ref(&temp), "dev");
+    let checkout = temp.path().join(".haxelib/mylib/git");
+    assert_eq!(common::git_head(&checkout), tip);
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("update")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All dependencies are up to date"));
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("check")
+        .assert()
+        .success();
+    assert_eq!(common::git_head(&checkout), tip);
+}
+
+#[test]
+fn update_fails_after_applying_what_it_could_look_up() {
+    let (_source, repo, first, second) = common::local_git_repo_with_two_releases();
+    let temp = common::project_with_hmm_json(&format!(
+        r#"{{"dependencies": [
+            {{"name": "mylib", "type": "git", "url": "{}", "ref": "{first}"}},
+            {{"name": "gone", "type": "git", "url": "file:///nonexistent/gone", "ref": "main"}}
+        ]}}"#,
+        common::file_url(&repo)
+    ));
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("update")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("up to date").not())
+        .stderr(predicate::str::contains("couldn't be checked for updates"))
+        .stderr(predicate::str::contains("gone"));
+
+    // hmm.json is saved sorted, so `gone` comes first
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.path().join("hmm.json")).unwrap())
+            .unwrap();
+    assert_eq!(json["dependencies"][1]["name"], "mylib");
+    assert_eq!(json["dependencies"][1]["ref"], second.as_str());
+    assert_eq!(json["dependencies"][0]["ref"], "main");
+}
+
+#[test]
+fn update_with_only_failed_lookups_is_not_up_to_date() {
+    let temp = common::project_pinning("file:///nonexistent/gone", "main");
+
+    cargo_bin_cmd!("hmm-rs")
+        .current_dir(temp.path())
+        .arg("update")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("up to date").not())
+        .stderr(predicate::str::contains(
+            "Failed to look up updates for 1 dependencies",
+        ));
+}
@@ -1,10 +1,9 @@
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
 
 #[test]
 fn upgrade_check_prints_current_version() {
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .args(["upgrade", "--check"])
         .assert()
         .stdout(predicate::str::contains("Current version: v"));
@@ -12,8 +11,7 @@ fn upgrade_check_prints_current_version() {
 
 #[test]
 fn self_update_alias_works() {
-    Command::cargo_bin("hmm-rs")
-        .unwrap()
+    cargo_bin_cmd!("hmm-rs")
         .args(["self-update", "--check"])
         .assert()
         .stdout(predicate::str::contains("Current version: v"));
@@ -47,12 +47,24 @@ pub fn project_with_installed_haxelibs(json: &str, libs: &[(&str, &str)]) -> Tem
     temp
 }
 
+/// Writes the haxelib.json of an installed haxelib, with the given `dependencies` object
+pub fn write_installed_haxelib_json(temp: &TempDir, name: &str, version: &str, dependencies: &str) {
+    let lib_name = name.replace(".", ",");
+    let version_dir = version.replace(".", ",");
+    temp.child(format!(".haxelib/{lib_name}/{version_dir}/haxelib.json"))
+        .write_str(&format!(
+            r#"{{"name": "{name}", "version": "{version}", "dependencies": {dependencies}}}"#
+        ))
+        .unwrap();
+}
+
 /// Reads a sample fixture file content
 pub fn sample_fixture_content(name: &str) -> String {
     std::fs::read_to_string(get_samples_dir().join(name)).unwrap()
 }
 
-fn run_git(repo: &std::path::Path, args: &[&str]) {
+/// Runs git in `repo`, asserting it succeeds
+pub fn run_git(repo: &std::path::Path, args: &[&str]) {
     let status = std::process::Command::new("git")
         .args(["-C", repo.to_str().unwrap()])
         .args(args)
@@ -80,7 +92,131 @@ pub fn local_git_repo_with_lib_subdir(subdir: &str) -> (TempDir, PathBuf) {
     (temp, repo_path)
 }
 
+/// Full commit id of HEAD in `repo`
+pub fn git_head(repo: &std::path::Path) -> String {
+    let output = std::process::Command::new("git")
+        .args(["-C", repo.to_str().unwrap(), "rev-parse", "HEAD"])
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// A repo on branch main with two commits, tagged v1.0.0 and v1.1.0.
+/// Returns the temp dir, the repo path and both commit ids
+pub fn local_git_repo_with_two_releases() -> (TempDir, PathBuf, String, String) {
+    let (temp, repo) = local_git_repo_with_lib_subdir("");
+    let first = git_head(&repo);
+    run_git(&repo, &["tag", "v1.0.0"]);
+    std::fs::write(repo.join("README.md"), "changed\n").unwrap();
+    run_git(&repo, &["commit", "-qam", "second"]);
+    run_git(&repo, &["tag", "-a", "v1.1.0", "-m", "release"]);
+    let second = git_head(&repo);
+    (temp, repo, first, second)
+}
+
+/// A project whose hmm.json has a single git dependency `mylib` on `url`, pinned to `git_ref`
+pub fn project_pinning(url: &str, git_ref: &str) -> TempDir {
+    project_with_hmm_json(&format!(
+        r#"{{"dependencies": [{{"name": "mylib", "type": "git", "url": "{url}", "ref": "{git_ref}"}}]}}"#
+    ))
+}
+
 /// Returns a `file://` clone URL for a local repo path.
 pub fn file_url(path: &std::path::Path) -> String {
     format!("file://{}", path.to_str().unwrap())
 }
+
+/// Builds an in-memory zip archive laid out like a haxelib download.
+pub fn haxelib_zip(files: &[(&str, &str)]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, content) in files {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// A user cache ($HMM_CACHE_DIR) holding a haxelib zip for each `(name, version, dependencies)`
+pub fn cache_with_haxelibs(libs: &[(&str, &str, &str)]) -> TempDir {
+    let cache = TempDir::new().unwrap();
+    for (name, version, dependencies) in libs {
+        let haxelib_json =
+            format!(r#"{{"name":"{name}","version":"{version}","dependencies":{dependencies}}}"#);
+        cache
+            .child(format!("haxelib/{name}/{version}/0123abcd.zip"))
+            .write_binary(&haxelib_zip(&[("haxelib.json", &haxelib_json)]))
+            .unwrap();
+    }
+    cache
+}
+
+/// A local HTTP server answering every `GET <path>` with `respond(path)`, a status line
+/// like `200 OK` and a body. Returns its base url, e.g. `http://127.0.0.1:1234`
+pub fn http_server(respond: impl Fn(&str) -> (&'static str, Vec<u8>) + Send + 'static) -> String {
+    raw_http_server(move |request| {
+        let path = request.split(' ').nth(1).unwrap_or("/");
+        let (status, body) = respond(path);
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len()
+        )
+        .into_bytes();
+        response.extend(body);
+        response
+    })
+}
+
+/// A local HTTP server handing the request line and headers of every request to `respond`,
+/// and writing back exactly the bytes it returns before closing the connection. Useful to
+/// send bodies shorter than their `Content-Length`, or without one at all
+pub fn raw_http_server(respond: impl Fn(&str) -> Vec<u8> + Send + 'static) -> String {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut request = String::new();
+            while reader.read_line(&mut request).unwrap() > 0 && !request.ends_with("\r\n\r\n") {}
+            let response = respond(&request);
+            let mut stream = reader.into_inner();
+            let _ = stream.write_all(&response);
+        }
+    });
+    url
+}
+
+/// Creates a local mercurial repo with `haxelib.json` inside `subdir`, and a single commit.
+pub fn local_hg_repo_with_lib_subdir(subdir: &str) -> (TempDir, PathBuf) {
+    let temp = TempDir::new().unwrap();
+    let repo_path = temp.path().join("mylib-hg");
+    std::fs::create_dir_all(repo_path.join(subdir)).unwrap();
+    std::fs::write(
+        repo_path.join(subdir).join("haxelib.json"),
+        "{\"name\":\"mylib\"}\n",
+    )
+    .unwrap();
+
+    let hg = |args: &[&str]| {
+        let status = std::process::Command::new("hg")
+            .args(["--repository", repo_path.to_str().unwrap()])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "hg {:?} failed", args);
+    };
+    let status = std::process::Command::new("hg")
+        .args(["init", repo_path.to_str().unwrap()])
+        .status()
+        .unwrap();
+    assert!(status.success(), "hg init failed");
+    hg(&["add"]);
+    hg(&["commit", "--user", "test", "--message", "init"]);
+    (temp, repo_path)
+}